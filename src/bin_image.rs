@@ -1,27 +1,24 @@
 #[cfg(not(feature = "improved_ysc_whh"))]
-use crate::common::{calculate_ap_and_bp, SubIter};
+use crate::common::SubIter;
+use crate::common::NEIGHBOR_OFFSETS;
+use crate::neighborhood::Neighborhood;
+use crate::text::TextFormat;
 use std::convert::TryFrom;
-#[cfg(test)]
 use std::fmt::Display;
-#[cfg(test)]
 use std::fs::OpenOptions;
-#[cfg(test)]
-use std::io::prelude::*;
-#[cfg(test)]
-use std::io::BufReader;
 use std::io::{Error, ErrorKind};
 use std::ops::Sub;
-#[cfg(test)]
 use std::path::PathBuf;
+use std::str::FromStr;
 
-#[derive(Clone)]
+/// A binary image stored row by row. `true` pixels are the foreground.
+#[derive(Clone, Debug, PartialEq)]
 pub struct BinImage {
     pixels: Vec<Vec<bool>>,
 }
 
 impl BinImage {
     /// Creating a new image for a given width and height
-    #[cfg(any(test, not(feature = "improved_ysc_whh")))]
     pub fn new(width: usize, height: usize, fill_color: bool) -> BinImage {
         let mut pixels: Vec<Vec<bool>> = vec![];
 
@@ -38,6 +35,7 @@ impl BinImage {
         BinImage { pixels }
     }
 
+    #[allow(clippy::get_first)]
     pub fn get_width(&self) -> usize {
         match self.pixels.get(0) {
             Some(row) => row.len(),
@@ -76,48 +74,63 @@ impl BinImage {
         &self.pixels
     }
 
-    pub fn get_neighbors(
-        &self,
-        x: usize,
-        y: usize,
-    ) -> (bool, bool, bool, bool, bool, bool, bool, bool, bool) {
-        (
-            self.get_value(x, y).unwrap_or(false),
-            if y == 0 {
-                false
-            } else {
-                self.get_value(x, y - 1).unwrap_or(false)
-            },
-            if y == 0 {
-                false
-            } else {
-                self.get_value(x + 1, y - 1).unwrap_or(false)
-            },
-            self.get_value(x + 1, y).unwrap_or(false),
-            self.get_value(x + 1, y + 1).unwrap_or(false),
-            self.get_value(x, y + 1).unwrap_or(false),
-            if x == 0 {
-                false
-            } else {
-                self.get_value(x - 1, y + 1).unwrap_or(false)
-            },
-            if x == 0 {
-                false
-            } else {
-                self.get_value(x - 1, y).unwrap_or(false)
-            },
-            if x == 0 || y == 0 {
-                false
-            } else {
-                self.get_value(x - 1, y - 1).unwrap_or(false)
-            },
-        )
+    /// Iterating over the pixels row by row as `(x, y, value)`
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, bool)> + '_ {
+        self.rows().enumerate().flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .map(move |(x, val)| (x, y, val.to_owned()))
+        })
+    }
+
+    /// Iterating over mutable pixels row by row as `(x, y, value)`
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (usize, usize, &mut bool)> + '_ {
+        self.pixels
+            .iter_mut()
+            .enumerate()
+            .flat_map(|(y, row)| row.iter_mut().enumerate().map(move |(x, val)| (x, y, val)))
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[bool]> + '_ {
+        self.pixels.iter().map(|row| row.as_slice())
+    }
+
+    /// Coordinates of the foreground pixels, row by row
+    pub fn foreground(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.iter()
+            .filter(|(_, _, val)| *val)
+            .map(|(x, y, _)| (x, y))
+    }
+
+    /// Coordinates and neighborhoods of all pixels, row by row
+    pub fn neighborhoods(&self) -> impl Iterator<Item = (usize, usize, Neighborhood)> + '_ {
+        self.iter()
+            .map(move |(x, y, _)| (x, y, self.get_neighbors(x, y)))
+    }
+
+    /// The pixel and its neighbors. Pixels outside of the image are
+    /// background.
+    pub fn get_neighbors(&self, x: usize, y: usize) -> Neighborhood {
+        let value = |dx: isize, dy: isize| {
+            let nx = x as isize + dx;
+            let ny = y as isize + dy;
+            nx >= 0 && ny >= 0 && self.get_value(nx as usize, ny as usize).unwrap_or(false)
+        };
+
+        let mut neighbors = [false; 8];
+        for (p, (dx, dy)) in neighbors.iter_mut().zip(NEIGHBOR_OFFSETS.iter()) {
+            *p = value(*dx, *dy);
+        }
+
+        Neighborhood::from_array(value(0, 0), neighbors)
     }
 
     #[cfg(not(feature = "improved_ysc_whh"))]
-    pub fn sub_iter(&self, mode: SubIter, x: usize, y: usize) -> bool {
-        let (_, p2, p3, p4, p5, p6, p7, p8, p9) = self.get_neighbors(x, y);
-        let (a_p, b_p) = calculate_ap_and_bp(p2, p3, p4, p5, p6, p7, p8, p9);
+    #[allow(clippy::manual_range_contains)]
+    pub(crate) fn sub_iter(&self, mode: SubIter, x: usize, y: usize) -> bool {
+        let n = self.get_neighbors(x, y);
+        let (p2, p4, p6, p8) = (n.p2(), n.p4(), n.p6(), n.p8());
+        let (a_p, b_p) = (n.transition_count(), n.count());
 
         let a = 2 <= b_p && b_p <= 6;
         let b = a_p == 1;
@@ -157,7 +170,7 @@ impl Iterator for BinImageIntoIter {
         let height = self.bin_image.get_height();
         let width = self.bin_image.get_width();
 
-        if height - 1 < self.y && self.x == 0 {
+        if self.y >= height || width == 0 {
             return None;
         }
 
@@ -205,141 +218,42 @@ impl Sub<BinImage> for BinImage {
     type Output = BinImage;
 
     fn sub(self, rhs: BinImage) -> BinImage {
-        let mut new_img = self.clone();
-
-        let iter = rhs.into_iter();
+        let mut new_img = self;
 
-        for (x, y, val) in iter {
-            if val {
-                new_img.set_value(x, y, false).unwrap();
-            }
+        for (x, y) in rhs.foreground() {
+            new_img.set_value(x, y, false).unwrap();
         }
 
         new_img
     }
 }
 
-#[cfg(test)]
+// Rendering an image as a grid of `X` and `_` with coordinate rulers
 impl Display for BinImage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut display_str = String::new();
-
-        let pixels = self.get_pixels();
-        let width = self.get_width();
-        let height = self.get_height();
-
-        fn count_digit(number: usize) -> usize {
-            let mut count = 0;
-            let mut n = number;
-
-            while n != 0 {
-                n = n / 10;
-                count += 1;
-            }
-            return count;
-        }
-
-        fn getdigit(number: usize, n: usize) -> usize {
-            let base: usize = 10;
-            let mut r = number / (base.pow(n as u32));
-            r = r % 10;
-            r
-        }
-
-        let x_dig = count_digit(width - 1);
-        let y_dig = count_digit(height - 1);
-        let base: usize = 10;
-
-        for dig in 0..x_dig {
-            for _ in 0..y_dig {
-                display_str.push(' ');
-            }
-            display_str.push('|');
-            for i in 0..width {
-                let digit = if i >= base.pow((x_dig - dig - 1) as u32) {
-                    let digi = getdigit(i, x_dig - dig - 1);
-                    digi.to_string()
-                } else {
-                    String::from(if x_dig - dig == 1 && i == 0 { "0" } else { " " })
-                };
-
-                display_str.push_str(&digit);
-                display_str.push('|');
-            }
-            display_str.push('\n');
-        }
+        let text = TextFormat::grid()
+            .render(self)
+            .map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", text)
+    }
+}
 
-        for (y, row) in pixels.iter().enumerate() {
-            for dig in 0..y_dig {
-                let digit = if y >= base.pow((x_dig - dig - 1) as u32) {
-                    let digi = getdigit(y, x_dig - dig - 1);
-                    digi.to_string()
-                } else {
-                    String::from(if x_dig - dig == 1 && y == 0 { "0" } else { " " })
-                };
+// Parsing an image of `1` and `0`, see `TextFormat`
+impl FromStr for BinImage {
+    type Err = Error;
 
-                display_str.push_str(&digit);
-            }
-            display_str.push('|');
-
-            for col in row.iter() {
-                if col.to_owned() {
-                    display_str.push('X');
-                } else {
-                    display_str.push('_');
-                }
-                display_str.push('|')
-            }
-            display_str.push('\n');
-        }
-        write!(f, "{}", display_str)
+    fn from_str(text: &str) -> Result<BinImage, Error> {
+        TextFormat::default().parse(text)
     }
 }
 
-#[cfg(test)]
+// Reading an image of `1` and `0` from a file, see `TextFormat`
 impl TryFrom<PathBuf> for BinImage {
     type Error = Error;
 
     fn try_from(path: PathBuf) -> Result<BinImage, Error> {
-        let opened = OpenOptions::new().read(true).open(path);
-
-        match opened {
-            Ok(file) => {
-                let mut pixels: Vec<Vec<bool>> = vec![];
-
-                let reader = BufReader::new(file);
-
-                let mut last_row = 0;
-                for l in reader.lines() {
-                    pixels.push(vec![]);
-                    last_row += 1;
-
-                    match l {
-                        Ok(line) => {
-                            for c in line.chars() {
-                                match c {
-                                    '1' => {
-                                        pixels.get_mut(last_row - 1).unwrap().push(true);
-                                    }
-                                    '0' => {
-                                        pixels.get_mut(last_row - 1).unwrap().push(false);
-                                    }
-                                    _ => {}
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            return Err(e);
-                        }
-                    }
-                }
-
-                let img_created = BinImage::try_from(pixels);
-
-                img_created
-            }
-            Err(e) => Err(e),
-        }
+        let file = OpenOptions::new().read(true).open(path)?;
+        TextFormat::default().read(file)
     }
 }
 
@@ -352,16 +266,68 @@ mod test {
 
         assert_eq!(
             img.get_neighbors(4, 5),
-            (true, false, true, true, true, false, true, true, true)
+            Neighborhood::from_array(true, [false, true, true, true, false, true, true, true])
         );
         assert_eq!(
             img.get_neighbors(0, 5),
-            (true, false, true, true, true, false, false, false, false)
+            Neighborhood::from_array(true, [false, true, true, true, false, false, false, false])
         );
         assert_eq!(
             img.get_neighbors(1, 3),
-            (false, true, false, false, false, true, false, false, false)
+            Neighborhood::from_array(
+                false,
+                [true, false, false, false, true, false, false, false]
+            )
+        );
+    }
+
+    #[test]
+    fn test_iterators() {
+        let mut img = BinImage::try_from(PathBuf::from("./test_data/test_sub.txt")).unwrap();
+
+        assert_eq!(img.iter().count(), 12);
+        assert!(img.iter().eq(img.clone().into_iter()));
+        assert_eq!(img.rows().nth(1).unwrap(), &[false, true, false, true][..]);
+        assert_eq!(
+            img.foreground().collect::<Vec<_>>(),
+            vec![
+                (0, 0),
+                (2, 0),
+                (3, 0),
+                (1, 1),
+                (3, 1),
+                (0, 2),
+                (1, 2),
+                (3, 2)
+            ]
+        );
+
+        let (x, y, n) = img.neighborhoods().nth(5).unwrap();
+        assert_eq!((x, y), (1, 1));
+        assert_eq!(n, img.get_neighbors(1, 1));
+
+        for (x, _, val) in img.iter_mut() {
+            *val = x == 0;
+        }
+        assert_eq!(
+            img.foreground().collect::<Vec<_>>(),
+            vec![(0, 0), (0, 1), (0, 2)]
         );
+
+        // Empty images have no pixels
+        let empty = BinImage::try_from(vec![]).unwrap();
+        assert_eq!(empty.iter().count(), 0);
+        assert_eq!(empty.into_iter().count(), 0);
+        assert_eq!(BinImage::new(0, 3, false).into_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_text() {
+        let img: BinImage = "101\n011\n".parse().unwrap();
+
+        assert_eq!(img.foreground().count(), 4);
+        assert_eq!(img.to_string(), " |0|1|2|\n0|X|_|X|\n1|_|X|X|\n");
+        assert!(BinImage::try_from(PathBuf::from("./test_data/missing.txt")).is_err());
     }
 
     #[test]
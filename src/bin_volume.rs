@@ -0,0 +1,149 @@
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind};
+
+/// A binary volume stored slice by slice, and every slice row by row. `true`
+/// voxels are the foreground.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BinVolume {
+    voxels: Vec<Vec<Vec<bool>>>,
+}
+
+impl BinVolume {
+    /// Creating a new volume for a given width, height and depth
+    pub fn new(width: usize, height: usize, depth: usize, fill_color: bool) -> BinVolume {
+        BinVolume {
+            voxels: vec![vec![vec![fill_color; width]; height]; depth],
+        }
+    }
+
+    pub fn get_width(&self) -> usize {
+        match self.voxels.first().and_then(|slice| slice.first()) {
+            Some(row) => row.len(),
+            None => 0,
+        }
+    }
+
+    pub fn get_height(&self) -> usize {
+        match self.voxels.first() {
+            Some(slice) => slice.len(),
+            None => 0,
+        }
+    }
+
+    pub fn get_depth(&self) -> usize {
+        self.voxels.len()
+    }
+
+    pub fn get_value(&self, x: usize, y: usize, z: usize) -> Result<bool, Error> {
+        match self.voxels.get(z) {
+            Some(slice) => match slice.get(y) {
+                Some(row) => match row.get(x) {
+                    Some(val) => Ok(val.to_owned()),
+                    None => Err(Error::new(ErrorKind::InvalidInput, "Invalid X coordinate")),
+                },
+                None => Err(Error::new(ErrorKind::InvalidInput, "Invalid Y coordinate")),
+            },
+            None => Err(Error::new(ErrorKind::InvalidInput, "Invalid Z coordinate")),
+        }
+    }
+
+    pub fn set_value(&mut self, x: usize, y: usize, z: usize, value: bool) -> Result<(), Error> {
+        match self.voxels.get_mut(z) {
+            Some(slice) => match slice.get_mut(y) {
+                Some(row) => match row.get_mut(x) {
+                    Some(val) => {
+                        *val = value;
+                        Ok(())
+                    }
+                    None => Err(Error::new(ErrorKind::InvalidInput, "Invalid X coordinate")),
+                },
+                None => Err(Error::new(ErrorKind::InvalidInput, "Invalid Y coordinate")),
+            },
+            None => Err(Error::new(ErrorKind::InvalidInput, "Invalid Z coordinate")),
+        }
+    }
+
+    pub fn get_voxels(&self) -> &Vec<Vec<Vec<bool>>> {
+        &self.voxels
+    }
+
+    /// Value of a voxel at an offset from another voxel. Voxels outside of the
+    /// volume are background.
+    pub fn get_offset_value(
+        &self,
+        x: usize,
+        y: usize,
+        z: usize,
+        dx: isize,
+        dy: isize,
+        dz: isize,
+    ) -> bool {
+        let nx = x as isize + dx;
+        let ny = y as isize + dy;
+        let nz = z as isize + dz;
+        if nx < 0 || ny < 0 || nz < 0 {
+            return false;
+        }
+
+        self.get_value(nx as usize, ny as usize, nz as usize)
+            .unwrap_or(false)
+    }
+
+    /// Number of foreground voxels
+    pub fn count_foreground(&self) -> usize {
+        self.voxels
+            .iter()
+            .flatten()
+            .flatten()
+            .filter(|val| **val)
+            .count()
+    }
+}
+
+// Creating a binary volume from binary data
+impl TryFrom<Vec<Vec<Vec<bool>>>> for BinVolume {
+    type Error = Error;
+
+    fn try_from(voxels: Vec<Vec<Vec<bool>>>) -> Result<BinVolume, Error> {
+        let height = voxels.first().map(|slice| slice.len()).unwrap_or(0);
+        let width = voxels
+            .first()
+            .and_then(|slice| slice.first())
+            .map(|row| row.len())
+            .unwrap_or(0);
+
+        let valid = voxels
+            .iter()
+            .all(|slice| slice.len() == height && slice.iter().all(|row| row.len() == width));
+        if !valid {
+            return Err(Error::new(ErrorKind::InvalidInput, "Invalid volume"));
+        }
+
+        Ok(BinVolume { voxels })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_volume() {
+        let mut volume = BinVolume::new(4, 3, 2, false);
+
+        assert_eq!(volume.get_width(), 4);
+        assert_eq!(volume.get_height(), 3);
+        assert_eq!(volume.get_depth(), 2);
+
+        volume.set_value(3, 2, 1, true).unwrap();
+        assert!(volume.get_value(3, 2, 1).unwrap());
+        assert!(volume.get_offset_value(2, 1, 0, 1, 1, 1));
+        assert!(!volume.get_offset_value(0, 0, 0, -1, 0, 0));
+        assert_eq!(volume.count_foreground(), 1);
+        assert!(volume.set_value(0, 0, 2, true).is_err());
+        assert!(volume.get_value(4, 0, 0).is_err());
+
+        assert!(BinVolume::try_from(vec![vec![vec![true; 2]; 2], vec![vec![true; 3]; 2]]).is_err());
+        assert_eq!(BinVolume::try_from(vec![]).unwrap().get_width(), 0);
+    }
+}
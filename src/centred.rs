@@ -0,0 +1,185 @@
+// Thinning in the order of the distance to the background
+use crate::bin_image::BinImage;
+use crate::common::NEIGHBOR_OFFSETS;
+use crate::distance::distance_transform;
+use crate::options::ThinOptions;
+use crate::thickness::remove_redundant_pixels_where;
+#[cfg(test)]
+use crate::topology::verify_topology;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind};
+#[cfg(test)]
+use std::path::PathBuf;
+
+// Whether a pixel is a local maximum of the distance map
+fn is_ridge(distance: &[Vec<f64>], x: usize, y: usize) -> bool {
+    NEIGHBOR_OFFSETS.iter().all(|(dx, dy)| {
+        let nx = x as isize + dx;
+        let ny = y as isize + dy;
+        if nx < 0 || ny < 0 {
+            return true;
+        }
+        match distance
+            .get(ny as usize)
+            .and_then(|row| row.get(nx as usize))
+        {
+            Some(d) => *d <= distance[y][x],
+            None => true,
+        }
+    })
+}
+
+// A pixel is deleted when removing it keeps the connectivity, unless it is an
+// end point on a ridge of the distance map
+fn is_deletable(image: &BinImage, distance: &[Vec<f64>], x: usize, y: usize) -> bool {
+    let n = image.get_neighbors(x, y);
+    if !n.is_simple() {
+        return false;
+    }
+
+    n.count() >= 2 || !is_ridge(distance, x, y)
+}
+
+/// Thinning by deleting pixels in the order of their distance to the
+/// background, so the skeleton is centred in thick strokes. Pixels at the
+/// same distance are deleted in raster order.
+///
+/// Every deleted pixel is a simple point, so the topology is kept, and end
+/// points are only kept on the ridge of the distance map. The skeleton is
+/// always one pixel thick. The region and anchors of `options` are used like
+/// in `imgthin_with_options`, and `max_iterations` is rejected.
+pub fn imgthin_centred(
+    pixels: Vec<Vec<bool>>,
+    options: &ThinOptions,
+) -> Result<Vec<Vec<bool>>, Error> {
+    if options.max_iterations.is_some() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Centred thinning has no iterations to limit",
+        ));
+    }
+
+    let mut image = BinImage::try_from(pixels)?;
+    let (x0, y0, x1, y1) = options.region_bounds(&image)?;
+    let distance = distance_transform(&image);
+
+    // Distances are square roots of integers, so the squares are exact keys
+    let key = |x: usize, y: usize| {
+        let d = distance[y][x];
+        Reverse(((d * d).round() as u64, y, x))
+    };
+
+    let mut queued = vec![vec![false; image.get_width()]; image.get_height()];
+    let mut heap = BinaryHeap::new();
+
+    for (y, row) in queued.iter_mut().enumerate().take(y1).skip(y0) {
+        for (x, queued) in row.iter_mut().enumerate().take(x1).skip(x0) {
+            let n = image.get_neighbors(x, y);
+            // Interior pixels are queued once their neighbors are deleted
+            if n.centre()
+                && !(n.north() && n.east() && n.south() && n.west())
+                && options.can_delete(x, y)
+            {
+                *queued = true;
+                heap.push(key(x, y));
+            }
+        }
+    }
+
+    while let Some(Reverse((_, y, x))) = heap.pop() {
+        queued[y][x] = false;
+        if !is_deletable(&image, &distance, x, y) {
+            continue;
+        }
+
+        image.set_value(x, y, false)?;
+
+        for (dx, dy) in NEIGHBOR_OFFSETS.iter() {
+            let nx = x as isize + dx;
+            let ny = y as isize + dy;
+            if nx < 0 || ny < 0 {
+                continue;
+            }
+            let (nx, ny) = (nx as usize, ny as usize);
+            if image.get_value(nx, ny).unwrap_or(false)
+                && !queued[ny][nx]
+                && options.can_delete(nx, ny)
+            {
+                queued[ny][nx] = true;
+                heap.push(key(nx, ny));
+            }
+        }
+    }
+
+    if options.unit_thickness {
+        remove_redundant_pixels_where(&mut image, (x0, y0, x1, y1), |x, y| {
+            options.can_delete(x, y)
+        });
+    }
+
+    Ok(image.get_pixels().to_vec())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::thickness::check_thickness;
+
+    #[test]
+    fn test_centred_bar() {
+        // A bar 5 pixels thick is thinned to its middle row
+        let mut pixels = vec![vec![false; 17]; 7];
+        for row in pixels.iter_mut().skip(1).take(5) {
+            for val in row.iter_mut().skip(1).take(15) {
+                *val = true;
+            }
+        }
+
+        let thinned = imgthin_centred(pixels, &ThinOptions::default()).unwrap();
+
+        for (y, row) in thinned.iter().enumerate() {
+            for (x, val) in row.iter().enumerate() {
+                assert_eq!(*val, y == 3 && (3..=13).contains(&x), "({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_centred_char_b() {
+        let img = BinImage::try_from(PathBuf::from("./test_data/b_char.txt")).unwrap();
+
+        let thinned = imgthin_centred(img.get_pixels().clone(), &ThinOptions::default()).unwrap();
+        let thinned = BinImage::try_from(thinned).unwrap();
+
+        assert!(verify_topology(&img, &thinned).unwrap().is_preserved());
+        assert!(check_thickness(&thinned).is_unit_thick());
+    }
+
+    #[test]
+    fn test_centred_anchors() {
+        let pixels = vec![vec![true; 4]; 4];
+        let mut anchors = BinImage::new(4, 4, false);
+        anchors.set_value(0, 0, true).unwrap();
+        anchors.set_value(3, 3, true).unwrap();
+
+        let options = ThinOptions {
+            anchors: Some(anchors),
+            ..ThinOptions::default()
+        };
+        let thinned = imgthin_centred(pixels, &options).unwrap();
+
+        assert!(thinned[0][0] && thinned[3][3]);
+        let thinned = BinImage::try_from(thinned).unwrap();
+        assert!(verify_topology(&BinImage::new(4, 4, true), &thinned)
+            .unwrap()
+            .is_preserved());
+
+        let invalid = ThinOptions {
+            max_iterations: Some(1),
+            ..ThinOptions::default()
+        };
+        assert!(imgthin_centred(vec![vec![true; 4]; 4], &invalid).is_err());
+    }
+}
@@ -0,0 +1,245 @@
+// Freeman chain codes of thinned images
+use crate::bin_image::BinImage;
+use crate::common::NEIGHBOR_OFFSETS;
+use crate::skeleton::trace_skeleton;
+#[cfg(test)]
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind};
+#[cfg(test)]
+use std::path::PathBuf;
+
+/// An 8-direction Freeman chain code. The directions follow the neighbor
+/// order of `BinImage::get_neighbors`, starting from the north and going
+/// clockwise: `0` is P2 (north), `1` is P3 (north-east), .. `7` is P9
+/// (north-west).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChainCode {
+    pub start: (usize, usize),
+    pub codes: Vec<u8>,
+}
+
+impl ChainCode {
+    /// Whether the chain comes back to the start pixel. Chains which are not
+    /// valid, see `points`, are not closed.
+    pub fn is_closed(&self) -> bool {
+        !self.codes.is_empty()
+            && self
+                .points()
+                .is_ok_and(|points| points.last() == Some(&self.start))
+    }
+
+    /// Pixels visited by the chain, including the start pixel. Fails for
+    /// codes above 7 and for steps going below zero.
+    pub fn points(&self) -> Result<Vec<(usize, usize)>, Error> {
+        let mut points = vec![self.start];
+        let (mut x, mut y) = (self.start.0 as isize, self.start.1 as isize);
+
+        for code in self.codes.iter() {
+            if *code > 7 {
+                return Err(Error::new(ErrorKind::InvalidInput, "Invalid chain code"));
+            }
+            let (dx, dy) = NEIGHBOR_OFFSETS[*code as usize];
+            x += dx;
+            y += dy;
+            if x < 0 || y < 0 {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "Chain code goes outside of the image",
+                ));
+            }
+            points.push((x as usize, y as usize));
+        }
+
+        Ok(points)
+    }
+
+    /// Number of steps in every direction
+    pub fn histogram(&self) -> [usize; 8] {
+        let mut histogram = [0; 8];
+        for code in self.codes.iter() {
+            histogram[*code as usize % 8] += 1;
+        }
+        histogram
+    }
+
+    /// Clockwise turns between consecutive steps, modulo 8. Closed chains also
+    /// get the turn from the last step to the first one.
+    pub fn differences(&self) -> Vec<u8> {
+        // Codes above 7 are taken modulo 8, like in `histogram`
+        let turn = |from: u8, to: u8| (to % 8 + 8 - from % 8) % 8;
+
+        let mut differences: Vec<u8> = self.codes.windows(2).map(|w| turn(w[0], w[1])).collect();
+
+        if self.codes.len() > 1 && self.is_closed() {
+            differences.push(turn(self.codes[self.codes.len() - 1], self.codes[0]));
+        }
+
+        differences
+    }
+
+    /// Length of the chain, counting diagonal steps as `sqrt(2)`
+    pub fn length(&self) -> f64 {
+        self.codes
+            .iter()
+            .map(|c| {
+                if c % 2 == 1 {
+                    std::f64::consts::SQRT_2
+                } else {
+                    1.0
+                }
+            })
+            .sum()
+    }
+}
+
+fn direction(from: (usize, usize), to: (usize, usize)) -> u8 {
+    let offset = (
+        to.0 as isize - from.0 as isize,
+        to.1 as isize - from.1 as isize,
+    );
+    NEIGHBOR_OFFSETS.iter().position(|o| *o == offset).unwrap() as u8
+}
+
+/// Encoding a thinned image as chain codes. Every traced edge gives one
+/// chain, starting from an end point or a junction. Closed loops without
+/// nodes start from their top-left pixel. Isolated pixels give empty chains.
+pub fn encode_chain_codes(image: &BinImage) -> Vec<ChainCode> {
+    let graph = trace_skeleton(image);
+
+    let mut chains: Vec<ChainCode> = graph
+        .edges
+        .iter()
+        .map(|edge| ChainCode {
+            start: edge.pixels[0],
+            codes: edge
+                .pixels
+                .windows(2)
+                .map(|w| direction(w[0], w[1]))
+                .collect(),
+        })
+        .collect();
+
+    chains.extend(
+        graph
+            .nodes
+            .iter()
+            .filter(|n| n.degree == 0)
+            .map(|n| ChainCode {
+                start: (n.x, n.y),
+                codes: vec![],
+            }),
+    );
+
+    chains
+}
+
+/// Drawing chain codes into a new image of the given size
+pub fn decode_chain_codes(
+    chains: &[ChainCode],
+    width: usize,
+    height: usize,
+) -> Result<BinImage, Error> {
+    let mut image = BinImage::new(width, height, false);
+
+    for chain in chains.iter() {
+        for (x, y) in chain.points()? {
+            image.set_value(x, y, true)?;
+        }
+    }
+
+    Ok(image)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encode_line() {
+        let img = BinImage::try_from(vec![
+            vec![true, true, false, false],
+            vec![false, false, true, false],
+            vec![false, false, true, false],
+        ])
+        .unwrap();
+
+        let chains = encode_chain_codes(&img);
+
+        assert_eq!(
+            chains,
+            vec![ChainCode {
+                start: (0, 0),
+                codes: vec![2, 3, 4]
+            }]
+        );
+        assert!(!chains[0].is_closed());
+        assert_eq!(chains[0].histogram(), [0, 0, 1, 1, 1, 0, 0, 0]);
+        assert_eq!(chains[0].differences(), vec![1, 1]);
+        assert!((chains[0].length() - (2.0 + std::f64::consts::SQRT_2)).abs() < 1e-9);
+
+        let wrapped = ChainCode {
+            start: (0, 0),
+            codes: vec![255, 250, 0],
+        };
+        assert_eq!(wrapped.differences(), vec![3, 6]);
+    }
+
+    #[test]
+    fn test_encode_loop() {
+        let img = BinImage::try_from(vec![
+            vec![false, true, true, false],
+            vec![true, false, false, true],
+            vec![false, true, true, false],
+        ])
+        .unwrap();
+
+        let chains = encode_chain_codes(&img);
+
+        assert_eq!(chains.len(), 1);
+        assert_eq!(chains[0].start, (1, 0));
+        assert!(chains[0].is_closed());
+        assert_eq!(chains[0].codes.len(), 6);
+        assert_eq!(chains[0].differences().len(), 6);
+        assert_eq!(
+            chains[0]
+                .differences()
+                .iter()
+                .map(|d| *d as usize)
+                .sum::<usize>()
+                % 8,
+            0
+        );
+    }
+
+    #[test]
+    fn test_decode_chain_codes() {
+        let img = BinImage::try_from(PathBuf::from("./test_data/b_char_thinned.txt")).unwrap();
+
+        let chains = encode_chain_codes(&img);
+        let decoded = decode_chain_codes(&chains, img.get_width(), img.get_height()).unwrap();
+
+        assert_eq!(decoded, img);
+
+        let outside = ChainCode {
+            start: (0, 0),
+            codes: vec![7],
+        };
+        assert!(outside.points().is_err());
+        assert!(decode_chain_codes(&[outside], 3, 3).is_err());
+
+        // Going out of the image and back is not a closed chain
+        let back = ChainCode {
+            start: (0, 0),
+            codes: vec![7, 3],
+        };
+        assert!(back.points().is_err());
+        assert!(!back.is_closed());
+
+        let invalid = ChainCode {
+            start: (1, 1),
+            codes: vec![8],
+        };
+        assert!(invalid.points().is_err());
+        assert!(decode_chain_codes(&[invalid], 3, 3).is_err());
+    }
+}
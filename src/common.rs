@@ -1,56 +1,176 @@
 // Common methods to improved and default algorithm
+use crate::bin_image::BinImage;
+use crate::options::ThinOptions;
+use crate::report::ThinReport;
+use crate::thickness::remove_redundant_pixels_where;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::io::Error;
+use std::time::Instant;
+
 #[derive(Clone, Debug)]
 pub enum SubIter {
     First,
     Second,
 }
 
-pub fn calculate_ap_and_bp(
-    p2: bool,
-    p3: bool,
-    p4: bool,
-    p5: bool,
-    p6: bool,
-    p7: bool,
-    p8: bool,
-    p9: bool,
-) -> (usize, usize) {
-    let mut arr = vec![p2, p3, p4, p5, p6, p7, p8, p9];
-    let b_p = arr.iter().map(|p| if *p { 1 } else { 0 }).sum::<usize>();
-
-    arr.push(p2);
-
-    let mut a_p = 0;
-    let mut prev_p = true;
-
-    for p in arr.iter() {
-        if !prev_p && p.to_owned() {
-            a_p += 1;
-        }
-        prev_p = p.to_owned();
+// Offsets of the neighbors P2, P3, .. P9 from the center pixel
+pub const NEIGHBOR_OFFSETS: [(isize, isize); 8] = [
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+];
+
+// Taking the first pixel out of every group of pixels in `deleted` which is a
+// whole 8-connected component of the image, so deleting them in parallel does
+// not remove the component entirely. `deleted` is in raster order, so the
+// first pixel is the top left one. Groups with a pixel for which `is_cut` is
+// true may go on outside of the image, and are deleted as they are.
+pub(crate) fn keep_components<F: Fn(usize, usize) -> bool>(
+    image: &BinImage,
+    deleted: &mut Vec<(usize, usize)>,
+    is_cut: F,
+) {
+    let group: HashSet<(usize, usize)> = deleted.iter().copied().collect();
+    let mut seen = HashSet::new();
+    let mut kept = HashSet::new();
+
+    for start in deleted.iter() {
+        if !seen.insert(*start) {
+            continue;
+        }
+
+        let mut whole = true;
+        let mut stack = vec![*start];
+        while let Some((x, y)) = stack.pop() {
+            whole &= !is_cut(x, y);
+
+            for (dx, dy) in NEIGHBOR_OFFSETS.iter() {
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                if nx < 0 || ny < 0 || !image.get_value(nx as usize, ny as usize).unwrap_or(false) {
+                    continue;
+                }
+
+                let neighbor = (nx as usize, ny as usize);
+                if !group.contains(&neighbor) {
+                    whole = false;
+                } else if seen.insert(neighbor) {
+                    stack.push(neighbor);
+                }
+            }
+        }
+
+        if whole {
+            kept.insert(*start);
+        }
     }
 
-    (a_p, b_p)
+    deleted.retain(|p| !kept.contains(p));
+}
+
+// The parts of a thinning algorithm which differ between the algorithms
+pub(crate) struct Conditions {
+    // Whether a pixel is deleted in the sub-iteration `s`, counted from 0
+    pub is_deletable: fn(&BinImage, usize, usize, usize, &ThinOptions) -> bool,
+    // Whether thinning stops after the sub-iteration `s` deleted `deleted`
+    // pixels
+    pub is_converged: fn(usize, usize) -> bool,
+    // Whether `ThinOptions::sequential` is used
+    pub sequential: bool,
+    // Whether the parallel sub-iterations keep a pixel of the components they
+    // would delete entirely
+    pub keep_components: bool,
 }
 
-#[cfg(test)]
-mod test {
-
-    use super::*;
-
-    #[test]
-    fn test_calculate_ap_bp() {
-        assert_eq!(
-            calculate_ap_and_bp(true, true, true, true, true, true, true, true),
-            (0, 8)
-        );
-        assert_eq!(
-            calculate_ap_and_bp(true, true, false, false, true, false, true, true),
-            (2, 5)
-        );
-        assert_eq!(
-            calculate_ap_and_bp(false, false, false, false, false, false, false, false),
-            (0, 0)
-        );
+// Thinning with the conditions of an algorithm, calling `deleted` with the
+// sub-iteration and the coordinates of every deleted pixel. Returns the
+// thinned pixels and the number of sub-iterations. The report is only filled
+// in, and the time only measured, when one is given.
+pub(crate) fn thin<F: FnMut(usize, usize, usize)>(
+    pixels: Vec<Vec<bool>>,
+    options: &ThinOptions,
+    conditions: &Conditions,
+    mut report: Option<&mut ThinReport>,
+    mut deleted: F,
+) -> Result<(Vec<Vec<bool>>, usize), Error> {
+    let start = report.is_some().then(Instant::now);
+    let mut image = BinImage::try_from(pixels)?;
+    let (x0, y0, x1, y1) = options.region_bounds(&image)?;
+    if let (Some(report), Some(start)) = (report.as_deref_mut(), start) {
+        report.initial_foreground = image.foreground().count();
+        report.timings.setup = start.elapsed();
+    }
+
+    let start = report.is_some().then(Instant::now);
+    let sequential = conditions.sequential && options.sequential;
+    let mut sub_iterations = 0;
+    let mut converged = true;
+
+    for s in 0.. {
+        if options.max_iterations.is_some_and(|max| s >= max * 2) {
+            converged = false;
+            break;
+        }
+
+        // Only the pixels in the region are evaluated, and anchors are kept
+        let mut m = vec![];
+
+        for y in y0..y1 {
+            for x in x0..x1 {
+                if (conditions.is_deletable)(&image, s, x, y, options) {
+                    // The sequential variant deletes at once, so the pixels
+                    // visited later see the deletion
+                    if sequential {
+                        image.set_value(x, y, false)?;
+                    }
+                    m.push((x, y));
+                }
+            }
+        }
+
+        // Deleting all pixels of a small component at once would remove it
+        if conditions.keep_components && !sequential {
+            keep_components(&image, &mut m, |_, _| false);
+        }
+
+        for (x, y) in m.iter() {
+            image.set_value(*x, *y, false)?;
+            deleted(s, *x, *y);
+        }
+
+        sub_iterations += 1;
+        if let Some(report) = report.as_deref_mut() {
+            report.deleted.push(m.len());
+        }
+        if (conditions.is_converged)(s, m.len()) {
+            break;
+        }
+    }
+
+    if let (Some(report), Some(start)) = (report.as_deref_mut(), start) {
+        report.sub_iterations = sub_iterations;
+        report.iterations = sub_iterations.div_ceil(2);
+        report.converged = converged;
+        report.timings.thinning = start.elapsed();
     }
+
+    let start = report.is_some().then(Instant::now);
+    let mut redundant_removed = 0;
+    if options.unit_thickness {
+        redundant_removed = remove_redundant_pixels_where(&mut image, (x0, y0, x1, y1), |x, y| {
+            options.can_delete(x, y)
+        });
+    }
+    if let (Some(report), Some(start)) = (report, start) {
+        report.redundant_removed = redundant_removed;
+        report.final_foreground = image.foreground().count();
+        report.timings.unit_thickness = start.elapsed();
+    }
+
+    Ok((image.get_pixels().to_vec(), sub_iterations))
 }
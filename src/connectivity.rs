@@ -0,0 +1,196 @@
+// Simple point tests and connectivity numbers of a 3 by 3 neighborhood.
+//
+// A neighborhood is an 8-bit mask of the neighbors P2, P3, .. P9. Bit 0 is
+// P2, the north neighbor, and the bits go clockwise up to bit 7, P9, the
+// north west neighbor. `Neighborhood::index` gives the mask of a pixel.
+
+// Neighbor `i` counted anticlockwise from the east neighbor, as in the
+// definitions of Hilditch and Yokoi. `i` may be up to 8 and wraps around.
+const fn anticlockwise(mask: u8, i: usize) -> bool {
+    // E, NE, N, NW, W, SW, S, SE as bits of the mask
+    const BITS: [u8; 8] = [2, 1, 0, 7, 6, 5, 4, 3];
+    mask & (1 << BITS[i % 8]) != 0
+}
+
+/// Number of foreground neighbors, `B(P)` of Zhang and Suen
+pub const fn neighbor_count(mask: u8) -> usize {
+    mask.count_ones() as usize
+}
+
+/// Number of background to foreground changes in the sequence P2, P3, .. P9,
+/// P2. This is `A(P)` of Zhang and Suen. The crossing number of Rutovitz
+/// counts the changes both ways, so it is twice this number.
+pub const fn transition_count(mask: u8) -> usize {
+    // Bit i of `mask & !rotated` is set when P(i+2) is set and the
+    // neighbor before it is not
+    let rotated = mask.rotate_left(1);
+    (mask & !rotated).count_ones() as usize
+}
+
+/// Crossing number of Hilditch, the number of times the foreground is
+/// crossed going around the pixel, with diagonal neighbors joining the
+/// foreground through a corner
+pub const fn hilditch_crossing_number(mask: u8) -> usize {
+    let mut count = 0;
+    let mut i = 0;
+    while i < 4 {
+        let k = i * 2;
+        if !anticlockwise(mask, k) && (anticlockwise(mask, k + 1) || anticlockwise(mask, k + 2)) {
+            count += 1;
+        }
+        i += 1;
+    }
+    count
+}
+
+// Connectivity number of Yokoi over the given values of the neighbors
+const fn yokoi(mask: u8) -> usize {
+    let mut count = 0;
+    let mut i = 0;
+    while i < 4 {
+        let k = i * 2;
+        if anticlockwise(mask, k) && !(anticlockwise(mask, k + 1) && anticlockwise(mask, k + 2)) {
+            count += 1;
+        }
+        i += 1;
+    }
+    count
+}
+
+/// Connectivity number of Yokoi for 4-connected foreground, the number of
+/// 4-connected foreground components the pixel joins
+pub const fn yokoi_4(mask: u8) -> usize {
+    yokoi(mask)
+}
+
+/// Connectivity number of Yokoi for 8-connected foreground, the number of
+/// 8-connected foreground components the pixel joins
+pub const fn yokoi_8(mask: u8) -> usize {
+    yokoi(!mask)
+}
+
+/// Whether deleting the pixel keeps the topology of an 8-connected
+/// foreground and a 4-connected background. Isolated pixels and interior
+/// pixels are not simple, but end points are.
+pub const fn is_simple_point(mask: u8) -> bool {
+    yokoi_8(mask) == 1
+}
+
+macro_rules! table {
+    ($type:ty, $f:expr) => {{
+        let mut table = [0 as $type; 256];
+        let mut mask = 0;
+        while mask < 256 {
+            table[mask] = $f(mask as u8) as $type;
+            mask += 1;
+        }
+        table
+    }};
+}
+
+/// `neighbor_count` of every mask
+pub const NEIGHBOR_COUNT_TABLE: [u8; 256] = table!(u8, neighbor_count);
+
+/// `transition_count` of every mask
+pub const TRANSITION_TABLE: [u8; 256] = table!(u8, transition_count);
+
+/// `hilditch_crossing_number` of every mask
+pub const HILDITCH_TABLE: [u8; 256] = table!(u8, hilditch_crossing_number);
+
+/// `yokoi_4` of every mask
+pub const YOKOI_4_TABLE: [u8; 256] = table!(u8, yokoi_4);
+
+/// `yokoi_8` of every mask
+pub const YOKOI_8_TABLE: [u8; 256] = table!(u8, yokoi_8);
+
+/// `is_simple_point` of every mask
+pub const SIMPLE_POINT_TABLE: [bool; 256] = {
+    let mut table = [false; 256];
+    let mut mask = 0;
+    while mask < 256 {
+        table[mask] = is_simple_point(mask as u8);
+        mask += 1;
+    }
+    table
+};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Mask from the neighbors P2, P3, .. P9
+    fn mask(neighbors: [u8; 8]) -> u8 {
+        neighbors
+            .iter()
+            .enumerate()
+            .fold(0, |mask, (i, p)| mask | (p << i))
+    }
+
+    #[test]
+    fn test_crossing_numbers() {
+        // Straight vertical line
+        let line = mask([1, 0, 0, 0, 1, 0, 0, 0]);
+        assert_eq!(neighbor_count(line), 2);
+        assert_eq!(transition_count(line), 2);
+        assert_eq!(hilditch_crossing_number(line), 2);
+
+        // An end point and an isolated pixel
+        assert_eq!(transition_count(mask([0, 0, 1, 0, 0, 0, 0, 0])), 1);
+        assert_eq!(transition_count(0), 0);
+        assert_eq!(transition_count(255), 0);
+
+        // N and NE touch, so Hilditch counts them once
+        let corner = mask([1, 1, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(transition_count(corner), 1);
+        assert_eq!(hilditch_crossing_number(corner), 1);
+
+        // Separate diagonal neighbors are crossed twice
+        let diagonals = mask([0, 1, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(transition_count(diagonals), 2);
+        assert_eq!(hilditch_crossing_number(diagonals), 2);
+    }
+
+    #[test]
+    fn test_yokoi() {
+        // Straight line
+        let line = mask([1, 0, 0, 0, 1, 0, 0, 0]);
+        assert_eq!(yokoi_4(line), 2);
+        assert_eq!(yokoi_8(line), 2);
+        // Staircase corner
+        let corner = mask([1, 0, 0, 0, 0, 0, 1, 0]);
+        assert_eq!(yokoi_8(corner), 1);
+        // Diagonal line is connected for 8-connectivity only
+        let diagonal = mask([0, 1, 0, 0, 0, 1, 0, 0]);
+        assert_eq!(yokoi_8(diagonal), 2);
+        assert_eq!(yokoi_4(diagonal), 0);
+        // Isolated and interior pixels
+        assert_eq!(yokoi_8(0), 0);
+        assert_eq!(yokoi_8(255), 0);
+    }
+
+    #[test]
+    fn test_simple_points() {
+        assert!(is_simple_point(mask([0, 0, 1, 0, 0, 0, 0, 0])));
+        assert!(is_simple_point(mask([1, 1, 1, 0, 0, 0, 0, 0])));
+        assert!(!is_simple_point(mask([1, 0, 0, 0, 1, 0, 0, 0])));
+        assert!(!is_simple_point(0));
+        assert!(!is_simple_point(255));
+        // Deleting the pixel would open a hole with the 4-connected background
+        assert!(!is_simple_point(mask([1, 0, 1, 1, 1, 1, 1, 1])));
+    }
+
+    #[test]
+    fn test_tables() {
+        for m in 0..=255u8 {
+            assert_eq!(NEIGHBOR_COUNT_TABLE[m as usize] as usize, neighbor_count(m));
+            assert_eq!(TRANSITION_TABLE[m as usize] as usize, transition_count(m));
+            assert_eq!(
+                HILDITCH_TABLE[m as usize] as usize,
+                hilditch_crossing_number(m)
+            );
+            assert_eq!(YOKOI_4_TABLE[m as usize] as usize, yokoi_4(m));
+            assert_eq!(YOKOI_8_TABLE[m as usize] as usize, yokoi_8(m));
+            assert_eq!(SIMPLE_POINT_TABLE[m as usize], is_simple_point(m));
+        }
+    }
+}
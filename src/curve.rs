@@ -0,0 +1,487 @@
+// Fitting traced skeleton edges with lines and cubic Bezier curves
+#[cfg(test)]
+use crate::bin_image::BinImage;
+use crate::skeleton::SkeletonGraph;
+#[cfg(test)]
+use crate::skeleton::{trace_skeleton, NodeKind, SkeletonEdge, SkeletonNode};
+#[cfg(test)]
+use crate::thickness::remove_redundant_pixels;
+use std::ops::RangeInclusive;
+
+type Point = (f64, f64);
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CurveSegment {
+    Line {
+        start: (f64, f64),
+        end: (f64, f64),
+    },
+    CubicBezier {
+        start: (f64, f64),
+        control1: (f64, f64),
+        control2: (f64, f64),
+        end: (f64, f64),
+    },
+}
+
+impl CurveSegment {
+    /// Point on the segment for a parameter between 0 and 1
+    pub fn point_at(&self, t: f64) -> (f64, f64) {
+        match self {
+            CurveSegment::Line { start, end } => add(*start, scale(sub(*end, *start), t)),
+            CurveSegment::CubicBezier {
+                start,
+                control1,
+                control2,
+                end,
+            } => bezier(&[*start, *control1, *control2, *end], t),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CurvePrimitive {
+    /// Index of the fitted edge in `SkeletonGraph::edges`
+    pub edge: usize,
+    /// Indexes of the pixels in `SkeletonEdge::pixels` covered by the segment
+    pub pixels: RangeInclusive<usize>,
+    pub segment: CurveSegment,
+}
+
+fn add(a: Point, b: Point) -> Point {
+    (a.0 + b.0, a.1 + b.1)
+}
+
+fn sub(a: Point, b: Point) -> Point {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+fn scale(a: Point, s: f64) -> Point {
+    (a.0 * s, a.1 * s)
+}
+
+fn dot(a: Point, b: Point) -> f64 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+fn distance(a: Point, b: Point) -> f64 {
+    dot(sub(a, b), sub(a, b)).sqrt()
+}
+
+fn normalize(a: Point) -> Point {
+    let len = dot(a, a).sqrt();
+    if len == 0.0 {
+        a
+    } else {
+        scale(a, 1.0 / len)
+    }
+}
+
+fn bezier(q: &[Point], t: f64) -> Point {
+    // De Casteljau for any degree
+    let mut points = q.to_vec();
+    for i in 1..q.len() {
+        for j in 0..q.len() - i {
+            points[j] = add(scale(points[j], 1.0 - t), scale(points[j + 1], t));
+        }
+    }
+    points[0]
+}
+
+fn distance_to_chord(p: Point, a: Point, b: Point) -> f64 {
+    let d = sub(b, a);
+    let len_sq = dot(d, d);
+    if len_sq == 0.0 {
+        return distance(p, a);
+    }
+    let t = (dot(sub(p, a), d) / len_sq).clamp(0.0, 1.0);
+    distance(p, add(a, scale(d, t)))
+}
+
+// Tangent from the first point towards the following points. Pixel paths
+// only have eight directions, so a few points are used to smooth it.
+fn tangent(points: &[Point], from: usize, towards: isize) -> Point {
+    let steps = 3.min(if towards > 0 {
+        points.len() - 1 - from
+    } else {
+        from
+    }) as isize;
+    normalize(sub(
+        points[(from as isize + towards * steps) as usize],
+        points[from],
+    ))
+}
+
+fn centre_tangent(points: &[Point], at: usize) -> Point {
+    let steps = 2.min(at).min(points.len() - 1 - at);
+    normalize(sub(points[at - steps], points[at + steps]))
+}
+
+fn chord_length_parameterize(points: &[Point]) -> Vec<f64> {
+    let mut u = vec![0.0];
+    for i in 1..points.len() {
+        u.push(u[i - 1] + distance(points[i], points[i - 1]));
+    }
+    let total = u[points.len() - 1];
+    u.iter().map(|v| v / total).collect()
+}
+
+// Least squares fit of the control points with fixed end tangents
+fn generate_bezier(points: &[Point], u: &[f64], t1: Point, t2: Point) -> [Point; 4] {
+    let first = points[0];
+    let last = points[points.len() - 1];
+
+    let mut c = [[0.0; 2]; 2];
+    let mut x = [0.0; 2];
+
+    for (p, t) in points.iter().zip(u.iter()) {
+        let mt = 1.0 - t;
+        let b0 = mt * mt * mt;
+        let b1 = 3.0 * t * mt * mt;
+        let b2 = 3.0 * t * t * mt;
+        let b3 = t * t * t;
+
+        let a0 = scale(t1, b1);
+        let a1 = scale(t2, b2);
+
+        c[0][0] += dot(a0, a0);
+        c[0][1] += dot(a0, a1);
+        c[1][1] += dot(a1, a1);
+
+        let tmp = sub(*p, add(scale(first, b0 + b1), scale(last, b2 + b3)));
+        x[0] += dot(a0, tmp);
+        x[1] += dot(a1, tmp);
+    }
+    c[1][0] = c[0][1];
+
+    let det_c0_c1 = c[0][0] * c[1][1] - c[1][0] * c[0][1];
+    let (alpha_l, alpha_r) = if det_c0_c1 == 0.0 {
+        (0.0, 0.0)
+    } else {
+        (
+            (x[0] * c[1][1] - x[1] * c[0][1]) / det_c0_c1,
+            (c[0][0] * x[1] - c[1][0] * x[0]) / det_c0_c1,
+        )
+    };
+
+    let seg_len = distance(first, last);
+    let epsilon = 1.0e-6 * seg_len;
+    let (alpha_l, alpha_r) = if alpha_l < epsilon || alpha_r < epsilon {
+        (seg_len / 3.0, seg_len / 3.0)
+    } else {
+        (alpha_l, alpha_r)
+    };
+
+    [
+        first,
+        add(first, scale(t1, alpha_l)),
+        add(last, scale(t2, alpha_r)),
+        last,
+    ]
+}
+
+fn max_error(points: &[Point], q: &[Point; 4], u: &[f64]) -> (f64, usize) {
+    (1..points.len() - 1)
+        .map(|i| (distance(bezier(q, u[i]), points[i]), i))
+        .fold((0.0, points.len() / 2), |max, cur| {
+            if cur.0 > max.0 {
+                cur
+            } else {
+                max
+            }
+        })
+}
+
+// Newton-Raphson step to find a better parameter for a point
+fn reparameterize(points: &[Point], q: &[Point; 4], u: &[f64]) -> Vec<f64> {
+    let q1: Vec<Point> = (0..3).map(|i| scale(sub(q[i + 1], q[i]), 3.0)).collect();
+    let q2: Vec<Point> = (0..2).map(|i| scale(sub(q1[i + 1], q1[i]), 2.0)).collect();
+
+    points
+        .iter()
+        .zip(u.iter())
+        .map(|(p, u)| {
+            let diff = sub(bezier(q, *u), *p);
+            let d1 = bezier(&q1, *u);
+            let d2 = bezier(&q2, *u);
+            let denominator = dot(d1, d1) + dot(diff, d2);
+            if denominator == 0.0 {
+                *u
+            } else {
+                (u - dot(diff, d1) / denominator).clamp(0.0, 1.0)
+            }
+        })
+        .collect()
+}
+
+fn fit_cubic(
+    points: &[Point],
+    offset: usize,
+    t1: Point,
+    t2: Point,
+    tolerance: f64,
+    out: &mut Vec<(RangeInclusive<usize>, CurveSegment)>,
+) {
+    let last = points.len() - 1;
+    let range = offset..=offset + last;
+
+    if points.len() == 2 {
+        let dist = distance(points[0], points[1]) / 3.0;
+        out.push((
+            range,
+            CurveSegment::CubicBezier {
+                start: points[0],
+                control1: add(points[0], scale(t1, dist)),
+                control2: add(points[1], scale(t2, dist)),
+                end: points[1],
+            },
+        ));
+        return;
+    }
+
+    let mut u = chord_length_parameterize(points);
+    let mut q = generate_bezier(points, &u, t1, t2);
+    let (mut error, mut split) = max_error(points, &q, &u);
+
+    if error > tolerance && error < tolerance * 4.0 {
+        for _ in 0..4 {
+            u = reparameterize(points, &q, &u);
+            q = generate_bezier(points, &u, t1, t2);
+            let (e, s) = max_error(points, &q, &u);
+            error = e;
+            split = s;
+            if error <= tolerance {
+                break;
+            }
+        }
+    }
+
+    if error <= tolerance {
+        out.push((
+            range,
+            CurveSegment::CubicBezier {
+                start: q[0],
+                control1: q[1],
+                control2: q[2],
+                end: q[3],
+            },
+        ));
+        return;
+    }
+
+    // Both halves share the tangent at the split point to keep G1 continuity
+    let centre = centre_tangent(points, split);
+    fit_cubic(&points[..=split], offset, t1, centre, tolerance, out);
+    fit_cubic(
+        &points[split..],
+        offset + split,
+        scale(centre, -1.0),
+        t2,
+        tolerance,
+        out,
+    );
+}
+
+/// Fitting every edge of a traced skeleton with lines and cubic Bezier
+/// curves. No pixel of an edge is farther than `tolerance` pixels from the
+/// fitted curve. Straight edges give a single line. Curves are continuous in
+/// tangent at the splits and at degree-2 nodes opening closed loops.
+pub fn fit_curves(graph: &SkeletonGraph, tolerance: f64) -> Vec<CurvePrimitive> {
+    let mut primitives = vec![];
+
+    for (index, edge) in graph.edges.iter().enumerate() {
+        let points: Vec<Point> = edge
+            .pixels
+            .iter()
+            .map(|(x, y)| (*x as f64, *y as f64))
+            .collect();
+        let last = points.len() - 1;
+        let mut fitted = vec![];
+
+        if edge.is_closed() && points.len() > 3 {
+            // Closed edges are split at the pixel farthest from the start
+            let split = (1..last)
+                .map(|i| (distance(points[i], points[0]), i))
+                .fold((0.0, 1), |max, cur| if cur.0 > max.0 { cur } else { max })
+                .1;
+
+            let (t1, t2) = if graph.nodes[edge.start].degree == 2 {
+                let wrapped: Vec<Point> = points[last - 2..last]
+                    .iter()
+                    .chain(points[..=2].iter())
+                    .cloned()
+                    .collect();
+                let t = centre_tangent(&wrapped, 2);
+                (scale(t, -1.0), t)
+            } else {
+                (tangent(&points, 0, 1), tangent(&points, last, -1))
+            };
+            let centre = centre_tangent(&points, split);
+
+            fit_cubic(&points[..=split], 0, t1, centre, tolerance, &mut fitted);
+            fit_cubic(
+                &points[split..],
+                split,
+                scale(centre, -1.0),
+                t2,
+                tolerance,
+                &mut fitted,
+            );
+        } else if points[1..last]
+            .iter()
+            .all(|p| distance_to_chord(*p, points[0], points[last]) <= tolerance)
+        {
+            fitted.push((
+                0..=last,
+                CurveSegment::Line {
+                    start: points[0],
+                    end: points[last],
+                },
+            ));
+        } else {
+            fit_cubic(
+                &points,
+                0,
+                tangent(&points, 0, 1),
+                tangent(&points, last, -1),
+                tolerance,
+                &mut fitted,
+            );
+        }
+
+        primitives.extend(fitted.into_iter().map(|(pixels, segment)| CurvePrimitive {
+            edge: index,
+            pixels,
+            segment,
+        }));
+    }
+
+    primitives
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn circle(radius: f64) -> BinImage {
+        let size = (radius * 2.0) as usize + 5;
+        let centre = size as f64 / 2.0;
+        let mut img = BinImage::new(size, size, false);
+        for y in 0..size {
+            for x in 0..size {
+                let d = ((x as f64 - centre).powi(2) + (y as f64 - centre).powi(2)).sqrt();
+                if (d - radius).abs() < 0.75 {
+                    img.set_value(x, y, true).unwrap();
+                }
+            }
+        }
+        remove_redundant_pixels(&mut img);
+        img
+    }
+
+    fn assert_within_tolerance(
+        graph: &SkeletonGraph,
+        primitives: &[CurvePrimitive],
+        tolerance: f64,
+    ) {
+        for primitive in primitives {
+            let pixels = &graph.edges[primitive.edge].pixels[primitive.pixels.clone()];
+            for (x, y) in pixels {
+                let closest = (0..=100)
+                    .map(|i| {
+                        distance(
+                            primitive.segment.point_at(i as f64 / 100.0),
+                            (*x as f64, *y as f64),
+                        )
+                    })
+                    .fold(f64::MAX, f64::min);
+                assert!(closest <= tolerance + 0.05);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fit_line() {
+        let graph = SkeletonGraph {
+            nodes: vec![
+                SkeletonNode {
+                    x: 0,
+                    y: 0,
+                    kind: NodeKind::End,
+                    degree: 1,
+                },
+                SkeletonNode {
+                    x: 5,
+                    y: 2,
+                    kind: NodeKind::End,
+                    degree: 1,
+                },
+            ],
+            edges: vec![SkeletonEdge {
+                start: 0,
+                end: 1,
+                pixels: vec![(0, 0), (1, 0), (2, 1), (3, 1), (4, 2), (5, 2)],
+            }],
+        };
+
+        let primitives = fit_curves(&graph, 1.0);
+
+        assert_eq!(
+            primitives,
+            vec![CurvePrimitive {
+                edge: 0,
+                pixels: 0..=5,
+                segment: CurveSegment::Line {
+                    start: (0.0, 0.0),
+                    end: (5.0, 2.0)
+                }
+            }]
+        );
+    }
+
+    #[test]
+    fn test_fit_circle() {
+        let graph = trace_skeleton(&circle(10.0));
+        assert_eq!(graph.edges.len(), 1);
+        assert_eq!(graph.nodes[0].kind, NodeKind::Loop);
+
+        let primitives = fit_curves(&graph, 1.0);
+        assert!(primitives.len() >= 2);
+        assert!(primitives.len() < graph.edges[0].pixels.len() / 4);
+
+        // Pixel ranges cover the whole edge
+        assert_eq!(*primitives[0].pixels.start(), 0);
+        assert_eq!(
+            *primitives[primitives.len() - 1].pixels.end(),
+            graph.edges[0].pixels.len() - 1
+        );
+        for pair in primitives.windows(2) {
+            assert_eq!(pair[0].pixels.end(), pair[1].pixels.start());
+        }
+
+        assert_within_tolerance(&graph, &primitives, 1.0);
+
+        // Tangents line up at the splits and at the loop node
+        let tangents: Vec<(Point, Point)> = primitives
+            .iter()
+            .map(|p| match p.segment {
+                CurveSegment::CubicBezier {
+                    start,
+                    control1,
+                    control2,
+                    end,
+                } => (
+                    normalize(sub(control1, start)),
+                    normalize(sub(end, control2)),
+                ),
+                CurveSegment::Line { start, end } => {
+                    (normalize(sub(end, start)), normalize(sub(end, start)))
+                }
+            })
+            .collect();
+        for i in 0..tangents.len() {
+            let next = tangents[(i + 1) % tangents.len()].0;
+            assert!(dot(tangents[i].1, next) > 0.999);
+        }
+    }
+}
@@ -1,55 +1,109 @@
 use crate::bin_image::*;
 use crate::common::*;
+use crate::neighborhood::Neighborhood;
+use crate::options::ThinOptions;
+#[cfg(test)]
+use crate::thickness::check_thickness;
+#[cfg(test)]
+use crate::topology::verify_topology;
+#[cfg(test)]
 use std::convert::TryFrom;
 use std::io::Error;
 #[cfg(test)]
 use std::path::PathBuf;
 
+// Options giving the result of `imgthin`, with the original conditions
+pub(crate) fn imgthin_options() -> ThinOptions {
+    ThinOptions {
+        preserve_small_shapes: false,
+        ..ThinOptions::default()
+    }
+}
+
 pub fn imgthin(pixels: Vec<Vec<bool>>) -> Result<Vec<Vec<bool>>, Error> {
-    let bin_image_result = BinImage::try_from(pixels);
-
-    match bin_image_result {
-        Ok(mut image_mut) => {
-            let mut c = 0;
-            let mut first = true;
-            let width = image_mut.get_width();
-            let height = image_mut.get_height();
-
-            while c > 0 || first {
-                first = false;
-                let sub_iters = vec![SubIter::First, SubIter::Second];
-
-                for sub_iter in sub_iters {
-                    if let SubIter::Second = sub_iter {
-                        c = 0;
-                    }
-
-                    let mut m = BinImage::new(width, height, false);
-                    let img_iter = image_mut.clone().into_iter();
-
-                    for (x, y, _) in img_iter {
-                        if image_mut.sub_iter(sub_iter.clone(), x, y)
-                            && image_mut.get_value(x, y).unwrap()
-                        {
-                            let _result = m.set_value(x, y, true);
-                            c += 1;
-                        }
-                    }
-
-                    image_mut = image_mut - m;
-
-                    if c == 0 {
-                        break;
-                    }
-                }
-            }
-            Ok(image_mut.get_pixels().to_vec())
-        }
-        Err(e) => Err(e),
+    imgthin_with_options(pixels, &imgthin_options())
+}
+
+// Whether a pixel is the top left pixel of a 2 by 2 block with nothing
+// around it. The original conditions delete the whole block, so this pixel is
+// kept.
+fn is_lone_block(image: &BinImage, x: usize, y: usize) -> bool {
+    let n = image.get_neighbors(x, y);
+    let is_background = |dx: isize, dy: isize| {
+        let (nx, ny) = (x as isize + dx, y as isize + dy);
+        nx < 0 || ny < 0 || !image.get_value(nx as usize, ny as usize).unwrap_or(false)
+    };
+
+    n.count() == 3
+        && n.east()
+        && n.south_east()
+        && n.south()
+        && (-1..=2).all(|i| {
+            is_background(i, -1)
+                && is_background(i, 2)
+                && is_background(-1, i)
+                && is_background(2, i)
+        })
+}
+
+// Whether a pixel is the outer pixel of a step of a diagonal line two pixels
+// thick. The original conditions never delete these, but delete the line from
+// both ends until nothing is left. Deleting them instead leaves one pixel on
+// every step, so the ends of the line have a single neighbor and are kept.
+fn is_diagonal_step(image: &BinImage, x: usize, y: usize) -> bool {
+    // The west, north west, south and south east neighbors of a line going
+    // down to the right
+    let step = Neighborhood::from_array(true, [false, false, false, true, true, false, true, true]);
+    let n = image.get_neighbors(x, y);
+
+    n == step || n == step.reflect_horizontal()
+}
+
+// Whether a pixel is deleted in the sub-iteration `s`, counted from 0
+fn is_deletable(image: &BinImage, s: usize, x: usize, y: usize, options: &ThinOptions) -> bool {
+    let sub_iter = if s.is_multiple_of(2) {
+        SubIter::First
+    } else {
+        SubIter::Second
+    };
+
+    // Pixels outside of the region and anchors are kept as they are
+    if !options.can_delete(x, y) || !image.get_value(x, y).unwrap() {
+        return false;
+    }
+
+    if options.preserve_small_shapes {
+        (image.sub_iter(sub_iter, x, y) && !is_lone_block(image, x, y))
+            || is_diagonal_step(image, x, y)
+    } else {
+        image.sub_iter(sub_iter, x, y)
     }
 }
 
+// Whether thinning stops after the sub-iteration `s` deleted `deleted` pixels.
+// Thinning stops when the second sub-iteration deletes nothing, or when there
+// is nothing to delete at all.
+fn is_converged(s: usize, deleted: usize) -> bool {
+    deleted == 0 && (s == 0 || s % 2 == 1)
+}
+
+// Conditions of the thinning of `imgthin_with_options`
+pub(crate) const CONDITIONS: Conditions = Conditions {
+    is_deletable,
+    is_converged,
+    sequential: false,
+    keep_components: false,
+};
+
+pub fn imgthin_with_options(
+    pixels: Vec<Vec<bool>>,
+    options: &ThinOptions,
+) -> Result<Vec<Vec<bool>>, Error> {
+    thin(pixels, options, &CONDITIONS, None, |_, _, _| {}).map(|(pixels, _)| pixels)
+}
+
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod test {
 
     use super::*;
@@ -72,6 +126,50 @@ mod test {
 
         let expect_img =
             BinImage::try_from(PathBuf::from("./test_data/b_char_thinned.txt")).unwrap();
-        assert_eq!(expect_img.get_pixels(), &thinned);
+        let thinned_img = BinImage::try_from(thinned).unwrap();
+        crate::assert_bin_image_eq!(expect_img, thinned_img);
+        assert!(verify_topology(&img, &thinned_img).unwrap().is_preserved());
+    }
+
+    #[test]
+    fn test_char_b_unit_thickness() {
+        let img = BinImage::try_from(PathBuf::from("./test_data/b_char.txt")).unwrap();
+
+        let options = ThinOptions {
+            unit_thickness: true,
+            ..Default::default()
+        };
+        let thinned = imgthin_with_options(img.get_pixels().to_vec(), &options).unwrap();
+        let thinned_img = BinImage::try_from(thinned).unwrap();
+
+        assert!(check_thickness(&thinned_img).is_unit_thick());
+        assert!(verify_topology(&img, &thinned_img).unwrap().is_preserved());
+    }
+
+    #[test]
+    fn test_small_shapes() {
+        let img = BinImage::try_from(PathBuf::from("./test_data/small_shapes.txt")).unwrap();
+        let expected =
+            BinImage::try_from(PathBuf::from("./test_data/small_shapes_thinned.txt")).unwrap();
+
+        let thinned =
+            imgthin_with_options(img.get_pixels().to_vec(), &ThinOptions::default()).unwrap();
+        crate::assert_bin_image_eq!(expected, BinImage::try_from(thinned).unwrap());
+        assert!(verify_topology(&img, &expected).unwrap().is_preserved());
+        assert!(check_thickness(&expected).is_unit_thick());
+
+        // Strokes without such shapes are thinned like with the original
+        // conditions
+        let b_char = BinImage::try_from(PathBuf::from("./test_data/b_char.txt")).unwrap();
+        assert_eq!(
+            imgthin_with_options(b_char.get_pixels().to_vec(), &ThinOptions::default()).unwrap(),
+            imgthin(b_char.get_pixels().to_vec()).unwrap()
+        );
+
+        // The original conditions delete the blocks and the diagonal lines
+        let original = BinImage::try_from(imgthin(img.get_pixels().to_vec()).unwrap()).unwrap();
+        let report = verify_topology(&img, &original).unwrap();
+        assert!(!report.is_preserved());
+        assert!(original.get_pixels()[8..].iter().flatten().all(|val| !val));
     }
 }
@@ -0,0 +1,443 @@
+// Comparing two binary images pixel by pixel
+use crate::bin_image::BinImage;
+use crate::text::write_grid;
+use std::fmt::Display;
+use std::io::{Error, ErrorKind, Write};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffPixel {
+    /// Background in both images
+    Neither,
+    /// Foreground in the left image only
+    OnlyLeft,
+    /// Foreground in the right image only
+    OnlyRight,
+    /// Foreground in both images
+    Both,
+}
+
+impl DiffPixel {
+    fn symbol(&self) -> char {
+        match self {
+            DiffPixel::Neither => '_',
+            DiffPixel::OnlyLeft => '-',
+            DiffPixel::OnlyRight => '+',
+            DiffPixel::Both => 'X',
+        }
+    }
+
+    fn colour(&self) -> [u8; 3] {
+        match self {
+            DiffPixel::Neither => [255, 255, 255],
+            DiffPixel::OnlyLeft => [220, 0, 0],
+            DiffPixel::OnlyRight => [0, 160, 0],
+            DiffPixel::Both => [0, 0, 0],
+        }
+    }
+}
+
+/// Pixel by pixel difference of two images of the same dimensions
+#[derive(Clone, Debug, PartialEq)]
+pub struct BinImageDiff {
+    pixels: Vec<Vec<DiffPixel>>,
+    pub only_left: usize,
+    pub only_right: usize,
+    pub both: usize,
+    /// Bounding rectangle of the differing pixels as `(x0, y0, x1, y1)` with
+    /// exclusive ends, `None` when the images are identical
+    pub bounds: Option<(usize, usize, usize, usize)>,
+}
+
+/// Comparing two images of the same dimensions
+pub fn diff_images(left: &BinImage, right: &BinImage) -> Result<BinImageDiff, Error> {
+    if left.get_width() != right.get_width() || left.get_height() != right.get_height() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Images have different dimensions",
+        ));
+    }
+
+    let mut diff = BinImageDiff {
+        pixels: vec![vec![DiffPixel::Neither; left.get_width()]; left.get_height()],
+        only_left: 0,
+        only_right: 0,
+        both: 0,
+        bounds: None,
+    };
+
+    for ((x, y, l), (_, _, r)) in left.iter().zip(right.iter()) {
+        let pixel = match (l, r) {
+            (false, false) => continue,
+            (true, true) => {
+                diff.both += 1;
+                DiffPixel::Both
+            }
+            (true, false) => {
+                diff.only_left += 1;
+                DiffPixel::OnlyLeft
+            }
+            (false, true) => {
+                diff.only_right += 1;
+                DiffPixel::OnlyRight
+            }
+        };
+        diff.pixels[y][x] = pixel;
+
+        if l != r {
+            diff.bounds = Some(match diff.bounds {
+                Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x + 1), y1.max(y + 1)),
+                None => (x, y, x + 1, y + 1),
+            });
+        }
+    }
+
+    Ok(diff)
+}
+
+// Checksum of the PNG chunks
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in data.iter() {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+// Checksum of the zlib stream
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data.iter() {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    b << 16 | a
+}
+
+fn write_chunk<W: Write>(writer: &mut W, kind: &[u8], data: &[u8]) -> Result<(), Error> {
+    let mut chunk = kind.to_vec();
+    chunk.extend(data);
+
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(&chunk)?;
+    writer.write_all(&crc32(&chunk).to_be_bytes())
+}
+
+impl BinImageDiff {
+    pub fn get_width(&self) -> usize {
+        match self.pixels.first() {
+            Some(row) => row.len(),
+            None => 0,
+        }
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.pixels.len()
+    }
+
+    pub fn get_value(&self, x: usize, y: usize) -> Result<DiffPixel, Error> {
+        match self.pixels.get(y) {
+            Some(row) => match row.get(x) {
+                Some(val) => Ok(*val),
+                None => Err(Error::new(ErrorKind::InvalidInput, "Invalid X coordinate")),
+            },
+            None => Err(Error::new(ErrorKind::InvalidInput, "Invalid Y coordinate")),
+        }
+    }
+
+    pub fn get_pixels(&self) -> &Vec<Vec<DiffPixel>> {
+        &self.pixels
+    }
+
+    /// Number of pixels which are foreground in one image only
+    pub fn count_differences(&self) -> usize {
+        self.only_left + self.only_right
+    }
+
+    pub fn is_identical(&self) -> bool {
+        self.count_differences() == 0
+    }
+
+    /// Writing the difference as a text grid with coordinate rulers, like
+    /// `TextFormat::grid`. Pixels in both images are `X`, pixels only in the
+    /// left image `-` and pixels only in the right image `+`. The counts are
+    /// written first as `#` comments.
+    pub fn write_text<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        writeln!(
+            writer,
+            "# - only left: {}, + only right: {}, X both: {}",
+            self.only_left, self.only_right, self.both
+        )?;
+        if let Some((x0, y0, x1, y1)) = self.bounds {
+            writeln!(
+                writer,
+                "# differences in x {}..{}, y {}..{}",
+                x0, x1, y0, y1
+            )?;
+        }
+
+        write_grid(writer, self.get_width(), self.get_height(), |x, y| {
+            self.pixels[y][x].symbol()
+        })
+    }
+
+    /// Rendering the difference as a text grid, see `write_text`
+    pub fn to_text(&self) -> String {
+        let mut buffer = vec![];
+        self.write_text(&mut buffer).unwrap();
+
+        String::from_utf8(buffer).unwrap()
+    }
+
+    // Colours of the pixels row by row, every pixel scaled to a square of
+    // `scale` by `scale` pixels
+    fn to_rgb(&self, scale: usize) -> Vec<Vec<u8>> {
+        self.pixels
+            .iter()
+            .flat_map(|row| {
+                let line: Vec<u8> = row
+                    .iter()
+                    .flat_map(|pixel| {
+                        let colour = pixel.colour();
+                        (0..scale).flat_map(move |_| colour)
+                    })
+                    .collect();
+                vec![line; scale]
+            })
+            .collect()
+    }
+
+    // Dimensions of the image drawn with every pixel as a `scale` by `scale`
+    // square. Fails for a scale of 0 and for images too large to address.
+    fn scaled_size(&self, scale: usize) -> Result<(usize, usize), Error> {
+        if scale == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Scale is 0"));
+        }
+
+        // Every scaled pixel takes 3 bytes
+        let width = self.get_width().checked_mul(scale);
+        let height = self.get_height().checked_mul(scale);
+        let bytes = width
+            .zip(height)
+            .and_then(|(w, h)| w.checked_mul(h))
+            .and_then(|n| n.checked_mul(3));
+
+        match (width, height, bytes) {
+            (Some(width), Some(height), Some(_)) => Ok((width, height)),
+            _ => Err(Error::new(ErrorKind::InvalidInput, "Scale is too large")),
+        }
+    }
+
+    /// Writing the difference as a binary PPM image. Pixels in both images
+    /// are black, pixels only in the left image red and pixels only in the
+    /// right image green. Every pixel is drawn as a `scale` by `scale` square.
+    /// Fails when `scale` is 0 or the scaled image is too large.
+    pub fn write_ppm<W: Write>(&self, writer: &mut W, scale: usize) -> Result<(), Error> {
+        let (width, height) = self.scaled_size(scale)?;
+        write!(writer, "P6\n{} {}\n255\n", width, height)?;
+
+        for line in self.to_rgb(scale) {
+            writer.write_all(&line)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writing the difference as an uncompressed PNG image, with the colours
+    /// and the scale of `write_ppm`
+    pub fn write_png<W: Write>(&self, writer: &mut W, scale: usize) -> Result<(), Error> {
+        let (width, height) = self.scaled_size(scale)?;
+        if width == 0 || height == 0 || width > u32::MAX as usize || height > u32::MAX as usize {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Invalid image dimensions",
+            ));
+        }
+
+        writer.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+        let mut header = vec![];
+        header.extend((width as u32).to_be_bytes());
+        header.extend((height as u32).to_be_bytes());
+        // 8 bit RGB without interlacing
+        header.extend([8, 2, 0, 0, 0]);
+        write_chunk(writer, b"IHDR", &header)?;
+
+        // Every row starts with the filter type 0
+        let mut raw = vec![];
+        for line in self.to_rgb(scale) {
+            raw.push(0);
+            raw.extend(line);
+        }
+
+        // Zlib stream of stored deflate blocks
+        let mut data = vec![0x78, 0x01];
+        let blocks: Vec<&[u8]> = raw.chunks(0xFFFF).collect();
+        for (i, block) in blocks.iter().enumerate() {
+            data.push(if i + 1 == blocks.len() { 1 } else { 0 });
+            data.extend((block.len() as u16).to_le_bytes());
+            data.extend((!(block.len() as u16)).to_le_bytes());
+            data.extend(block.iter());
+        }
+        data.extend(adler32(&raw).to_be_bytes());
+        write_chunk(writer, b"IDAT", &data)?;
+
+        write_chunk(writer, b"IEND", &[])
+    }
+}
+
+impl Display for BinImageDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_text())
+    }
+}
+
+/// Asserting that two `BinImage`s are equal. On failure the panic message
+/// shows the difference as a text grid, see `BinImageDiff::write_text`. The
+/// left image is usually the expected one, so `-` marks missing pixels and
+/// `+` extra pixels.
+///
+/// ```rust
+/// use imgthin::{assert_bin_image_eq, BinImage};
+///
+/// let left = BinImage::new(3, 2, true);
+/// let right = BinImage::new(3, 2, true);
+/// assert_bin_image_eq!(left, right);
+/// ```
+#[macro_export]
+macro_rules! assert_bin_image_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert_bin_image_eq!($left, $right, "images are not equal")
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        match (&$left, &$right) {
+            (left, right) => match $crate::diff_images(left, right) {
+                Ok(diff) => {
+                    if !diff.is_identical() {
+                        panic!(
+                            "assertion failed: {}\n{} differing pixels\n{}",
+                            format_args!($($arg)+),
+                            diff.count_differences(),
+                            diff
+                        );
+                    }
+                }
+                Err(_) => panic!(
+                    "assertion failed: {}\nimages have different dimensions: {}x{} and {}x{}",
+                    format_args!($($arg)+),
+                    left.get_width(),
+                    left.get_height(),
+                    right.get_width(),
+                    right.get_height()
+                ),
+            },
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::convert::TryFrom;
+
+    fn images() -> (BinImage, BinImage) {
+        let left = "0110\n0110\n0000\n".parse().unwrap();
+        let right = "0100\n0110\n0001\n".parse().unwrap();
+        (left, right)
+    }
+
+    #[test]
+    fn test_diff() {
+        let (left, right) = images();
+
+        let diff = diff_images(&left, &right).unwrap();
+
+        assert_eq!((diff.only_left, diff.only_right, diff.both), (1, 1, 3));
+        assert_eq!(diff.count_differences(), 2);
+        assert_eq!(diff.bounds, Some((2, 0, 4, 3)));
+        assert_eq!(diff.get_value(2, 0).unwrap(), DiffPixel::OnlyLeft);
+        assert_eq!(diff.get_value(3, 2).unwrap(), DiffPixel::OnlyRight);
+        assert_eq!(diff.get_value(1, 1).unwrap(), DiffPixel::Both);
+        assert_eq!(diff.get_value(0, 0).unwrap(), DiffPixel::Neither);
+
+        let same = diff_images(&left, &left).unwrap();
+        assert!(same.is_identical());
+        assert_eq!(same.bounds, None);
+
+        assert!(diff_images(&left, &BinImage::new(3, 3, false)).is_err());
+    }
+
+    #[test]
+    fn test_to_text() {
+        let (left, right) = images();
+
+        assert_eq!(
+            diff_images(&left, &right).unwrap().to_text(),
+            concat!(
+                "# - only left: 1, + only right: 1, X both: 3\n",
+                "# differences in x 2..4, y 0..3\n",
+                " |0|1|2|3|\n",
+                "0|_|X|-|_|\n",
+                "1|_|X|X|_|\n",
+                "2|_|_|_|+|\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_ppm_and_png() {
+        let (left, right) = images();
+        let diff = diff_images(&left, &right).unwrap();
+
+        let mut ppm = vec![];
+        diff.write_ppm(&mut ppm, 2).unwrap();
+        assert!(ppm.starts_with(b"P6\n8 6\n255\n"));
+        assert_eq!(ppm.len(), 11 + 8 * 6 * 3);
+        // The pixel only in the left image at (2, 0) covers (4..6, 0..2)
+        assert_eq!(&ppm[11 + 4 * 3..11 + 5 * 3], &[220, 0, 0]);
+        assert_eq!(&ppm[11 + 8 * 3 + 5 * 3..11 + 8 * 3 + 6 * 3], &[220, 0, 0]);
+
+        let mut png = vec![];
+        diff.write_png(&mut png, 1).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x04\0\0\0\x03"));
+        assert!(png.ends_with(b"\0\0\0\0IEND\xae\x42\x60\x82"));
+        // Header, image data of 3 rows of 13 bytes, and the end
+        assert_eq!(png.len(), 8 + 25 + (12 + 2 + 5 + 39 + 4) + 12);
+
+        let empty = diff_images(&BinImage::new(0, 0, false), &BinImage::new(0, 0, false));
+        assert!(empty.unwrap().write_png(&mut vec![], 1).is_err());
+
+        // Scales of 0 and scales overflowing the dimensions are rejected
+        let mut buffer = vec![];
+        for scale in [0, usize::MAX / 2] {
+            let error = diff.write_ppm(&mut buffer, scale).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidInput);
+            let error = diff.write_png(&mut buffer, scale).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        }
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_assert_bin_image_eq() {
+        let (left, right) = images();
+        assert_bin_image_eq!(left, left.clone());
+
+        let message =
+            std::panic::catch_unwind(|| assert_bin_image_eq!(left, right, "char {}", 'b'))
+                .unwrap_err()
+                .downcast::<String>()
+                .unwrap();
+        assert!(message.starts_with("assertion failed: char b\n2 differing pixels\n"));
+        assert!(message.contains("0|_|X|-|_|"));
+
+        let other = BinImage::try_from(vec![vec![true]]).unwrap();
+        assert!(std::panic::catch_unwind(|| assert_bin_image_eq!(left, other)).is_err());
+    }
+}
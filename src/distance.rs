@@ -0,0 +1,117 @@
+// Euclidean distance transform of binary images
+use crate::bin_image::BinImage;
+
+// Squared distance transform of a sampled function in one dimension.
+// P. Felzenszwalb and D. Huttenlocher, Distance Transforms of Sampled
+// Functions.
+fn transform_1d(f: &[f64]) -> Vec<f64> {
+    let n = f.len();
+    let mut d = vec![0.0; n];
+    let mut v = vec![0; n];
+    let mut z = vec![0.0; n + 1];
+    let mut k = 0;
+
+    z[0] = f64::NEG_INFINITY;
+    z[1] = f64::INFINITY;
+
+    let intersection = |q: usize, p: usize| {
+        ((f[q] + (q * q) as f64) - (f[p] + (p * p) as f64)) / (2 * (q - p)) as f64
+    };
+
+    for q in 1..n {
+        let mut s = intersection(q, v[k]);
+        while s <= z[k] {
+            k -= 1;
+            s = intersection(q, v[k]);
+        }
+        k += 1;
+        v[k] = q;
+        z[k] = s;
+        z[k + 1] = f64::INFINITY;
+    }
+
+    k = 0;
+    for (q, d) in d.iter_mut().enumerate() {
+        while z[k + 1] < q as f64 {
+            k += 1;
+        }
+        let p = v[k];
+        *d = (q as f64 - p as f64).powi(2) + f[p];
+    }
+
+    d
+}
+
+/// Distance from every foreground pixel to the nearest background pixel.
+/// Pixels outside of the image are background, so a single foreground pixel
+/// has a distance of 1. Background pixels have a distance of 0.
+pub fn distance_transform(image: &BinImage) -> Vec<Vec<f64>> {
+    let width = image.get_width() + 2;
+    let height = image.get_height() + 2;
+    let pixels = image.get_pixels();
+
+    // A background border is added around the image. Foreground pixels start
+    // with a value larger than any squared distance in the image.
+    let far = ((width * width + height * height) * 2) as f64;
+    let mut grid = vec![vec![0.0; width]; height];
+    for (y, row) in pixels.iter().enumerate() {
+        for (x, val) in row.iter().enumerate() {
+            if *val {
+                grid[y + 1][x + 1] = far;
+            }
+        }
+    }
+
+    for x in 0..width {
+        let column: Vec<f64> = grid.iter().map(|row| row[x]).collect();
+        for (y, d) in transform_1d(&column).into_iter().enumerate() {
+            grid[y][x] = d;
+        }
+    }
+
+    for row in grid.iter_mut() {
+        *row = transform_1d(row);
+    }
+
+    grid[1..height - 1]
+        .iter()
+        .map(|row| row[1..width - 1].iter().map(|d| d.sqrt()).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_distance_transform() {
+        let mut img = BinImage::new(7, 5, false);
+        for y in 1..4 {
+            for x in 1..6 {
+                img.set_value(x, y, true).unwrap();
+            }
+        }
+        img.set_value(0, 4, true).unwrap();
+
+        let distance = distance_transform(&img);
+
+        assert_eq!(distance[0][0], 0.0);
+        assert_eq!(distance[1][1], 1.0);
+        assert_eq!(distance[2][1], 1.0);
+        assert_eq!(distance[2][2], 2.0);
+        assert_eq!(distance[2][3], 2.0);
+        assert_eq!(distance[4][0], 1.0);
+    }
+
+    #[test]
+    fn test_distance_transform_diagonal() {
+        let mut img = BinImage::new(9, 9, true);
+        img.set_value(0, 0, false).unwrap();
+
+        let distance = distance_transform(&img);
+
+        // Every pixel is next to the image border except the centre region
+        assert_eq!(distance[4][4], 5.0);
+        assert_eq!(distance[1][1], 2.0_f64.sqrt());
+    }
+}
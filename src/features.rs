@@ -0,0 +1,242 @@
+// Glyph features of thinned images for classifiers
+use crate::bin_image::BinImage;
+use crate::skeleton::{foreground_neighbors, trace_skeleton, NodeKind};
+use crate::topology::count_holes;
+#[cfg(test)]
+use std::convert::TryFrom;
+#[cfg(test)]
+use std::path::PathBuf;
+
+#[derive(Clone, Debug)]
+pub struct FeatureOptions {
+    /// Number of zones in each direction of the bounding box
+    pub grid: usize,
+    /// Number of end point positions in the feature vector
+    pub max_end_points: usize,
+}
+
+impl Default for FeatureOptions {
+    fn default() -> FeatureOptions {
+        FeatureOptions {
+            grid: 3,
+            max_end_points: 6,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SkeletonFeatures {
+    pub end_points: usize,
+    /// Groups of touching junction pixels are counted once
+    pub junctions: usize,
+    pub loops: usize,
+    /// Skeleton length in pixels, counting diagonal steps as `sqrt(2)`
+    pub length: f32,
+    /// Diagonal of the bounding box of the skeleton
+    pub diagonal: f32,
+    /// Share of the skeleton pixels in every zone, row by row
+    pub zone_pixels: Vec<f32>,
+    /// Share of the neighbor links in every zone, four orientations per zone:
+    /// vertical, rising diagonal, horizontal and falling diagonal
+    pub zone_directions: Vec<f32>,
+    /// End point positions relative to the bounding box, between 0 and 1
+    pub end_point_positions: Vec<(f32, f32)>,
+    max_end_points: usize,
+}
+
+impl SkeletonFeatures {
+    /// Fixed length feature vector. The length only depends on the options:
+    /// `4 + grid * grid * 5 + max_end_points * 2`. Missing end points are
+    /// filled with `-1`.
+    pub fn to_vec(&self) -> Vec<f32> {
+        let mut vector = vec![
+            self.end_points as f32,
+            self.junctions as f32,
+            self.loops as f32,
+            if self.diagonal > 0.0 {
+                self.length / self.diagonal
+            } else {
+                0.0
+            },
+        ];
+        vector.extend(self.zone_pixels.iter());
+        vector.extend(self.zone_directions.iter());
+
+        for i in 0..self.max_end_points {
+            let (x, y) = self
+                .end_point_positions
+                .get(i)
+                .cloned()
+                .unwrap_or((-1.0, -1.0));
+            vector.push(x);
+            vector.push(y);
+        }
+
+        vector
+    }
+}
+
+fn relative(value: usize, min: usize, max: usize) -> f32 {
+    if max == min {
+        0.5
+    } else {
+        (value - min) as f32 / (max - min) as f32
+    }
+}
+
+/// Extracting features from a thinned image. The image should be one pixel
+/// thick, see `remove_redundant_pixels`.
+pub fn extract_features(image: &BinImage, options: &FeatureOptions) -> SkeletonFeatures {
+    let grid = options.grid.max(1);
+    let graph = trace_skeleton(image);
+
+    let pixels: Vec<(usize, usize)> = image.foreground().collect();
+
+    let min_x = pixels.iter().map(|p| p.0).min().unwrap_or(0);
+    let max_x = pixels.iter().map(|p| p.0).max().unwrap_or(0);
+    let min_y = pixels.iter().map(|p| p.1).min().unwrap_or(0);
+    let max_y = pixels.iter().map(|p| p.1).max().unwrap_or(0);
+
+    let zone = |x: usize, y: usize| {
+        let zx = ((relative(x, min_x, max_x) * grid as f32) as usize).min(grid - 1);
+        let zy = ((relative(y, min_y, max_y) * grid as f32) as usize).min(grid - 1);
+        zy * grid + zx
+    };
+
+    let mut zone_pixels = vec![0.0; grid * grid];
+    let mut zone_directions = vec![0.0; grid * grid * 4];
+    let mut links = 0;
+
+    for (x, y) in pixels.iter() {
+        let z = zone(*x, *y);
+        zone_pixels[z] += 1.0;
+
+        for (nx, ny) in foreground_neighbors(image, *x, *y) {
+            let orientation = match (nx as isize - *x as isize, ny as isize - *y as isize) {
+                (0, _) => 0,
+                (dx, dy) if dx == -dy => 1,
+                (_, 0) => 2,
+                _ => 3,
+            };
+            zone_directions[z * 4 + orientation] += 1.0;
+            links += 1;
+        }
+    }
+
+    if !pixels.is_empty() {
+        zone_pixels
+            .iter_mut()
+            .for_each(|v| *v /= pixels.len() as f32);
+    }
+    if links > 0 {
+        zone_directions.iter_mut().for_each(|v| *v /= links as f32);
+    }
+
+    let end_point_positions: Vec<(f32, f32)> = graph
+        .nodes
+        .iter()
+        .filter(|n| n.kind == NodeKind::End)
+        .take(options.max_end_points)
+        .map(|n| (relative(n.x, min_x, max_x), relative(n.y, min_y, max_y)))
+        .collect();
+
+    // Touching junction pixels are merged
+    let junction_pixels: Vec<(usize, usize)> = graph
+        .nodes
+        .iter()
+        .filter(|n| n.kind == NodeKind::Junction)
+        .map(|n| (n.x, n.y))
+        .collect();
+    let junctions = junction_pixels
+        .iter()
+        .filter(|(x, y)| {
+            // Only the first pixel of a group in raster order is counted
+            !junction_pixels.iter().any(|(jx, jy)| {
+                (*jy < *y || (*jy == *y && *jx < *x))
+                    && (*jx as isize - *x as isize).abs() <= 1
+                    && (*jy as isize - *y as isize).abs() <= 1
+            })
+        })
+        .count();
+
+    // Every loop of a skeleton encloses a hole
+    let loops = if pixels.is_empty() {
+        0
+    } else {
+        count_holes(image)
+    };
+
+    SkeletonFeatures {
+        end_points: graph
+            .nodes
+            .iter()
+            .filter(|n| n.kind == NodeKind::End)
+            .count(),
+        junctions,
+        loops,
+        length: graph.edges.iter().map(|e| e.length()).sum::<f64>() as f32,
+        diagonal: (((max_x - min_x) as f32).powi(2) + ((max_y - min_y) as f32).powi(2)).sqrt(),
+        zone_pixels,
+        zone_directions,
+        end_point_positions,
+        max_end_points: options.max_end_points,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::thickness::remove_redundant_pixels;
+
+    #[test]
+    fn test_extract_char_b() {
+        let mut img = BinImage::try_from(PathBuf::from("./test_data/b_char_thinned.txt")).unwrap();
+        remove_redundant_pixels(&mut img);
+
+        let features = extract_features(&img, &FeatureOptions::default());
+
+        assert_eq!(features.end_points, 0);
+        assert_eq!(features.junctions, 2);
+        assert_eq!(features.loops, 2);
+        assert!(features.length > 60.0);
+        assert!((features.zone_pixels.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+        assert!((features.zone_directions.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+
+        let vector = features.to_vec();
+        assert_eq!(vector.len(), 4 + 9 * 5 + 6 * 2);
+        assert_eq!(vector[vector.len() - 1], -1.0);
+    }
+
+    #[test]
+    fn test_extract_line() {
+        let img = BinImage::try_from(vec![
+            vec![false, false, false, false, false],
+            vec![false, true, true, true, true],
+            vec![false, false, false, false, false],
+        ])
+        .unwrap();
+
+        let features = extract_features(
+            &img,
+            &FeatureOptions {
+                grid: 2,
+                max_end_points: 2,
+            },
+        );
+
+        assert_eq!(features.end_points, 2);
+        assert_eq!(features.junctions, 0);
+        assert_eq!(features.loops, 0);
+        assert_eq!(features.length, 3.0);
+        assert_eq!(features.end_point_positions, vec![(0.0, 0.5), (1.0, 0.5)]);
+        // Only horizontal links in the lower zones, as the line is centred
+        assert_eq!(
+            features.zone_directions[10] + features.zone_directions[14],
+            1.0
+        );
+
+        let vector = features.to_vec();
+        assert_eq!(vector.len(), 4 + 4 * 5 + 2 * 2);
+        assert_eq!(vector[3], 1.0);
+    }
+}
@@ -0,0 +1,265 @@
+// Exporting skeleton graphs as GeoJSON and WKT
+#[cfg(test)]
+use crate::bin_image::BinImage;
+#[cfg(test)]
+use crate::skeleton::trace_skeleton;
+use crate::skeleton::{SkeletonEdge, SkeletonGraph};
+#[cfg(test)]
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind, Write};
+
+/// Affine transform from pixel to world coordinates, with the six
+/// coefficients in the order of a GDAL geotransform:
+///
+/// ```text
+/// world_x = c[0] + pixel_x * c[1] + pixel_y * c[2]
+/// world_y = c[3] + pixel_x * c[4] + pixel_y * c[5]
+/// ```
+///
+/// Pixel coordinates are taken at the top left corner of a pixel, so the
+/// centre of the pixel `(x, y)` is at `(x + 0.5, y + 0.5)`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GeoTransform(pub [f64; 6]);
+
+impl GeoTransform {
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        let c = &self.0;
+        (c[0] + x * c[1] + y * c[2], c[3] + x * c[4] + y * c[5])
+    }
+}
+
+impl Default for GeoTransform {
+    /// World coordinates are pixel coordinates
+    fn default() -> GeoTransform {
+        GeoTransform([0.0, 1.0, 0.0, 0.0, 0.0, 1.0])
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GeoOptions {
+    /// Pixel to world transform. Without it the coordinates are pixel
+    /// coordinates.
+    pub transform: Option<GeoTransform>,
+    /// Exporting all edges as a single `MultiLineString` instead of one
+    /// `LineString` for every edge
+    pub multi_line: bool,
+}
+
+// World coordinates of the pixel centres of an edge
+fn edge_coordinates(edge: &SkeletonEdge, transform: &GeoTransform) -> Vec<(f64, f64)> {
+    edge.pixels
+        .iter()
+        .map(|(x, y)| transform.apply(*x as f64 + 0.5, *y as f64 + 0.5))
+        .collect()
+}
+
+fn line_length(coordinates: &[(f64, f64)]) -> f64 {
+    coordinates
+        .windows(2)
+        .map(|w| ((w[1].0 - w[0].0).powi(2) + (w[1].1 - w[0].1).powi(2)).sqrt())
+        .sum()
+}
+
+// Every line of the export, in world coordinates
+fn lines(graph: &SkeletonGraph, options: &GeoOptions) -> Result<Vec<Vec<(f64, f64)>>, Error> {
+    let transform = options.transform.unwrap_or_default();
+    if transform.0.iter().any(|c| !c.is_finite()) {
+        return Err(Error::new(ErrorKind::InvalidInput, "Invalid geotransform"));
+    }
+
+    Ok(graph
+        .edges
+        .iter()
+        .map(|edge| edge_coordinates(edge, &transform))
+        .collect())
+}
+
+fn json_positions(coordinates: &[(f64, f64)]) -> String {
+    let positions: Vec<String> = coordinates
+        .iter()
+        .map(|(x, y)| format!("[{},{}]", x, y))
+        .collect();
+    format!("[{}]", positions.join(","))
+}
+
+fn wkt_positions(coordinates: &[(f64, f64)]) -> String {
+    let positions: Vec<String> = coordinates
+        .iter()
+        .map(|(x, y)| format!("{} {}", x, y))
+        .collect();
+    format!("({})", positions.join(", "))
+}
+
+/// Writing the edges of a skeleton graph as a GeoJSON feature collection.
+///
+/// Every edge is a `LineString` feature with the degrees of its start and
+/// end nodes and its length in world units as properties. With
+/// `multi_line`, a single `MultiLineString` feature holds all edges, with
+/// the number of edges and the total length as properties.
+pub fn write_geojson<W: Write>(
+    writer: &mut W,
+    graph: &SkeletonGraph,
+    options: &GeoOptions,
+) -> Result<(), Error> {
+    let lines = lines(graph, options)?;
+
+    let features: Vec<String> = if options.multi_line {
+        let length: f64 = lines.iter().map(|l| line_length(l)).sum();
+        let positions: Vec<String> = lines.iter().map(|l| json_positions(l)).collect();
+        vec![format!(
+            "{{\"type\":\"Feature\",\
+             \"geometry\":{{\"type\":\"MultiLineString\",\"coordinates\":[{}]}},\
+             \"properties\":{{\"edges\":{},\"length\":{}}}}}",
+            positions.join(","),
+            lines.len(),
+            length
+        )]
+    } else {
+        graph
+            .edges
+            .iter()
+            .zip(lines.iter())
+            .map(|(edge, line)| {
+                format!(
+                    "{{\"type\":\"Feature\",\
+                     \"geometry\":{{\"type\":\"LineString\",\"coordinates\":{}}},\
+                     \"properties\":{{\"start_degree\":{},\"end_degree\":{},\"length\":{}}}}}",
+                    json_positions(line),
+                    graph.nodes[edge.start].degree,
+                    graph.nodes[edge.end].degree,
+                    line_length(line)
+                )
+            })
+            .collect()
+    };
+
+    writeln!(
+        writer,
+        "{{\"type\":\"FeatureCollection\",\"features\":[{}]}}",
+        features.join(",")
+    )
+}
+
+/// Rendering the edges of a skeleton graph as a GeoJSON string, see
+/// `write_geojson`
+pub fn to_geojson(graph: &SkeletonGraph, options: &GeoOptions) -> Result<String, Error> {
+    let mut buffer = vec![];
+    write_geojson(&mut buffer, graph, options)?;
+
+    String::from_utf8(buffer).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+/// Writing the edges of a skeleton graph as WKT, one `LINESTRING` per line
+/// in the order of `SkeletonGraph::edges`. With `multi_line`, a single
+/// `MULTILINESTRING` is written, which is `MULTILINESTRING EMPTY` without
+/// edges.
+pub fn write_wkt<W: Write>(
+    writer: &mut W,
+    graph: &SkeletonGraph,
+    options: &GeoOptions,
+) -> Result<(), Error> {
+    let lines = lines(graph, options)?;
+
+    if options.multi_line {
+        if lines.is_empty() {
+            return writeln!(writer, "MULTILINESTRING EMPTY");
+        }
+        let positions: Vec<String> = lines.iter().map(|l| wkt_positions(l)).collect();
+        return writeln!(writer, "MULTILINESTRING ({})", positions.join(", "));
+    }
+
+    for line in lines.iter() {
+        writeln!(writer, "LINESTRING {}", wkt_positions(line))?;
+    }
+
+    Ok(())
+}
+
+/// Rendering the edges of a skeleton graph as a WKT string, see `write_wkt`
+pub fn to_wkt(graph: &SkeletonGraph, options: &GeoOptions) -> Result<String, Error> {
+    let mut buffer = vec![];
+    write_wkt(&mut buffer, graph, options)?;
+
+    String::from_utf8(buffer).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn graph() -> SkeletonGraph {
+        // Three branches meeting at (2, 2)
+        trace_skeleton(
+            &BinImage::try_from(vec![
+                vec![true, false, false, false, true],
+                vec![false, true, false, true, false],
+                vec![false, false, true, false, false],
+                vec![false, false, true, false, false],
+                vec![false, false, true, false, false],
+            ])
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_geotransform() {
+        // 10 metre pixels, north up
+        let transform = GeoTransform([500000.0, 10.0, 0.0, 4000000.0, 0.0, -10.0]);
+
+        assert_eq!(transform.apply(0.0, 0.0), (500000.0, 4000000.0));
+        assert_eq!(transform.apply(1.5, 2.5), (500015.0, 3999975.0));
+        assert_eq!(GeoTransform::default().apply(1.5, 2.5), (1.5, 2.5));
+    }
+
+    #[test]
+    fn test_to_geojson() {
+        let graph = graph();
+        let geojson = to_geojson(&graph, &GeoOptions::default()).unwrap();
+
+        assert!(geojson.starts_with("{\"type\":\"FeatureCollection\",\"features\":["));
+        assert_eq!(geojson.matches("\"LineString\"").count(), 3);
+        assert!(geojson.contains(
+            "\"geometry\":{\"type\":\"LineString\",\"coordinates\":[[2.5,2.5],[2.5,3.5],[2.5,4.5]]},\
+             \"properties\":{\"start_degree\":3,\"end_degree\":1,\"length\":2}"
+        ));
+
+        let options = GeoOptions {
+            transform: Some(GeoTransform([100.0, 2.0, 0.0, 50.0, 0.0, -2.0])),
+            multi_line: true,
+        };
+        let geojson = to_geojson(&graph, &options).unwrap();
+        assert_eq!(geojson.matches("\"MultiLineString\"").count(), 1);
+        assert!(geojson.contains("[[101,49],[103,47],[105,45]]"));
+        let length = 8.0 * std::f64::consts::SQRT_2 + 4.0;
+        assert!(geojson.contains(&format!(
+            "\"properties\":{{\"edges\":3,\"length\":{}}}",
+            length
+        )));
+    }
+
+    #[test]
+    fn test_to_wkt() {
+        let graph = graph();
+
+        let wkt = to_wkt(&graph, &GeoOptions::default()).unwrap();
+        assert_eq!(wkt.lines().count(), 3);
+        assert!(wkt.contains("LINESTRING (2.5 2.5, 2.5 3.5, 2.5 4.5)\n"));
+
+        let options = GeoOptions {
+            multi_line: true,
+            ..GeoOptions::default()
+        };
+        let wkt = to_wkt(&graph, &options).unwrap();
+        assert!(wkt.starts_with("MULTILINESTRING ((0.5 0.5, 1.5 1.5, 2.5 2.5), ("));
+        assert_eq!(
+            to_wkt(&SkeletonGraph::default(), &options).unwrap(),
+            "MULTILINESTRING EMPTY\n"
+        );
+
+        let options = GeoOptions {
+            transform: Some(GeoTransform([f64::NAN, 1.0, 0.0, 0.0, 0.0, 1.0])),
+            ..GeoOptions::default()
+        };
+        assert!(to_wkt(&graph, &options).is_err());
+    }
+}
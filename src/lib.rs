@@ -15,10 +15,10 @@
 //! > very effective.
 //!
 //! - [A fast parallel algorithm for thinning digital patterns](https://www-prima.inrialpes.fr/perso/Tran/Draft/gateway.cfm.pdf)
-//!  [A modified fast parallel algorithm for thinning digital patterns](https://www.researchgate.net/publication/222456229)
-//! 
+//! - [A modified fast parallel algorithm for thinning digital patterns](https://www.researchgate.net/publication/222456229)
+//!
 //! # Installation
-//! 
+//!
 //! Add `imgthin` as a dependency to the `Cargo.toml` file.
 //!
 //! To use the original algorithm from Zhang and Suen:-
@@ -37,9 +37,9 @@
 //! imgthin = {version = "0.1.1", features=["improved_ysc_whh"]}
 //!
 //! ```
-//! 
+//!
 //! ## Usage
-//! 
+//!
 //! ```rust
 //! use imgthin::imgthin;
 //!
@@ -53,6 +53,50 @@
 //!
 //! ```
 //!
+//! ## Verifying the topology
+//!
+//! `verify_topology` compares the connected components and holes of the
+//! original image with the thinned image.
+//!
+//! ```rust
+//! use imgthin::{imgthin, verify_topology, BinImage};
+//! use std::convert::TryFrom;
+//!
+//! let pixels = vec!(
+//!     vec!(false, true, true, true, false),
+//!     vec!(false, true, true, true, false),
+//!     vec!(false, true, true, true, false)
+//! );
+//! let thinned = imgthin(pixels.clone()).unwrap();
+//!
+//! let report = verify_topology(
+//!     &BinImage::try_from(pixels).unwrap(),
+//!     &BinImage::try_from(thinned).unwrap()
+//! ).unwrap();
+//!
+//! assert!(report.is_preserved());
+//! ```
+//!
+//! ## Anchors
+//!
+//! Pixels marked in `ThinOptions::anchors` are never deleted, so a dot or a
+//! marked stroke centre survives thinning.
+//!
+//! ```rust
+//! use imgthin::{imgthin_with_options, BinImage, ThinOptions};
+//!
+//! let mut anchors = BinImage::new(2, 2, false);
+//! anchors.set_value(0, 0, true).unwrap();
+//!
+//! let options = ThinOptions {
+//!     anchors: Some(anchors),
+//!     ..ThinOptions::default()
+//! };
+//! let thinned = imgthin_with_options(vec![vec![true; 2]; 2], &options).unwrap();
+//!
+//! assert!(thinned[0][0]);
+//! ```
+//!
 
 #[cfg(feature = "improved_ysc_whh")]
 mod ysc_whh;
@@ -65,5 +109,78 @@ mod default;
 pub use default::*;
 
 mod bin_image;
+pub use bin_image::{BinImage, BinImageIntoIter};
+
+mod text;
+pub use text::{TextFormat, TextParseError};
+
+mod diff;
+pub use diff::{diff_images, BinImageDiff, DiffPixel};
+
+mod bin_volume;
+pub use bin_volume::BinVolume;
 
 mod common;
+
+mod connectivity;
+pub use connectivity::*;
+
+mod neighborhood;
+pub use neighborhood::Neighborhood;
+
+mod topology;
+pub use topology::*;
+
+mod thickness;
+pub use thickness::*;
+
+mod options;
+pub use options::*;
+
+mod report;
+pub use report::*;
+
+mod peel;
+pub use peel::{imgthin_with_peel_order, PeelOrder};
+
+mod centred;
+pub use centred::*;
+
+mod skeleton;
+pub use skeleton::*;
+
+mod polyline;
+pub use polyline::*;
+
+mod curve;
+pub use curve::*;
+
+mod chain_code;
+pub use chain_code::*;
+
+mod distance;
+pub use distance::*;
+
+mod stroke_width;
+pub use stroke_width::*;
+
+mod features;
+pub use features::*;
+
+mod loops;
+pub use loops::*;
+
+mod minutiae;
+pub use minutiae::*;
+
+mod svg;
+pub use svg::*;
+
+mod geo;
+pub use geo::*;
+
+mod volume;
+pub use volume::*;
+
+mod tiled;
+pub use tiled::*;
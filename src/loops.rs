@@ -0,0 +1,286 @@
+// Detecting closed loops in traced skeletons
+#[cfg(test)]
+use crate::bin_image::BinImage;
+#[cfg(test)]
+use crate::skeleton::trace_skeleton;
+use crate::skeleton::SkeletonGraph;
+#[cfg(test)]
+use crate::thickness::remove_redundant_pixels;
+#[cfg(test)]
+use std::convert::TryFrom;
+#[cfg(test)]
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SkeletonLoop {
+    /// Indexes of the edges in `SkeletonGraph::edges` forming the loop
+    pub edges: Vec<usize>,
+    /// Pixels of the loop in order. The first pixel is not repeated at the
+    /// end.
+    pub pixels: Vec<(usize, usize)>,
+    /// Area enclosed by the polygon through the pixel centres
+    pub area: f64,
+    /// Centroid of the enclosed area
+    pub centroid: (f64, f64),
+    /// Length of the loop, counting diagonal steps as `sqrt(2)`
+    pub length: f64,
+}
+
+// A step along an edge, forwards from `start` to `end` or backwards
+type Step = (usize, bool);
+
+// Shortest paths from a node to every other node. Returns the steps to reach
+// every node, or `None` for unreachable nodes.
+fn shortest_paths(graph: &SkeletonGraph, weights: &[f64], from: usize) -> Vec<Option<Vec<Step>>> {
+    let n = graph.nodes.len();
+    let mut distance = vec![f64::INFINITY; n];
+    let mut previous: Vec<Option<Step>> = vec![None; n];
+    let mut done = vec![false; n];
+    distance[from] = 0.0;
+
+    loop {
+        let current = (0..n)
+            .filter(|i| !done[*i] && distance[*i].is_finite())
+            .min_by(|a, b| distance[*a].partial_cmp(&distance[*b]).unwrap());
+        let current = match current {
+            Some(c) => c,
+            None => break,
+        };
+        done[current] = true;
+
+        for (i, edge) in graph.edges.iter().enumerate() {
+            let (next, forward) = if edge.start == current {
+                (edge.end, true)
+            } else if edge.end == current {
+                (edge.start, false)
+            } else {
+                continue;
+            };
+            if distance[current] + weights[i] < distance[next] {
+                distance[next] = distance[current] + weights[i];
+                previous[next] = Some((i, forward));
+            }
+        }
+    }
+
+    (0..n)
+        .map(|to| {
+            if !distance[to].is_finite() {
+                return None;
+            }
+            let mut steps = vec![];
+            let mut node = to;
+            while node != from {
+                let (edge, forward) = previous[node].unwrap();
+                steps.push((edge, forward));
+                node = if forward {
+                    graph.edges[edge].start
+                } else {
+                    graph.edges[edge].end
+                };
+            }
+            steps.reverse();
+            Some(steps)
+        })
+        .collect()
+}
+
+fn step_nodes(graph: &SkeletonGraph, steps: &[Step]) -> Vec<usize> {
+    steps
+        .iter()
+        .map(|(e, forward)| {
+            if *forward {
+                graph.edges[*e].end
+            } else {
+                graph.edges[*e].start
+            }
+        })
+        .collect()
+}
+
+fn build_loop(graph: &SkeletonGraph, steps: &[Step]) -> SkeletonLoop {
+    let mut pixels: Vec<(usize, usize)> = vec![];
+    for (edge, forward) in steps.iter() {
+        let mut edge_pixels = graph.edges[*edge].pixels.clone();
+        if !forward {
+            edge_pixels.reverse();
+        }
+        if !pixels.is_empty() {
+            edge_pixels.remove(0);
+        }
+        pixels.extend(edge_pixels);
+    }
+    // The ring closes on the first pixel
+    pixels.pop();
+
+    let points: Vec<(f64, f64)> = pixels.iter().map(|(x, y)| (*x as f64, *y as f64)).collect();
+    let mut area = 0.0;
+    let mut cx = 0.0;
+    let mut cy = 0.0;
+    for i in 0..points.len() {
+        let (x0, y0) = points[i];
+        let (x1, y1) = points[(i + 1) % points.len()];
+        let cross = x0 * y1 - x1 * y0;
+        area += cross;
+        cx += (x0 + x1) * cross;
+        cy += (y0 + y1) * cross;
+    }
+    area /= 2.0;
+
+    let centroid = if area.abs() > 1e-9 {
+        (cx / (6.0 * area), cy / (6.0 * area))
+    } else {
+        let n = points.len() as f64;
+        (
+            points.iter().map(|p| p.0).sum::<f64>() / n,
+            points.iter().map(|p| p.1).sum::<f64>() / n,
+        )
+    };
+
+    SkeletonLoop {
+        edges: steps.iter().map(|(e, _)| *e).collect(),
+        pixels,
+        area: area.abs(),
+        centroid,
+        length: steps.iter().map(|(e, _)| graph.edges[*e].length()).sum(),
+    }
+}
+
+// Reducing a cycle with the rows of the basis. Returns whether the cycle is
+// independent and adds it to the basis if so.
+fn add_to_basis(basis: &mut Vec<(usize, Vec<bool>)>, mut row: Vec<bool>) -> bool {
+    for (pivot, basis_row) in basis.iter() {
+        if row[*pivot] {
+            for (r, b) in row.iter_mut().zip(basis_row.iter()) {
+                *r ^= *b;
+            }
+        }
+    }
+
+    match row.iter().position(|v| *v) {
+        Some(pivot) => {
+            for (_, basis_row) in basis.iter_mut() {
+                if basis_row[pivot] {
+                    for (b, r) in basis_row.iter_mut().zip(row.iter()) {
+                        *b ^= *r;
+                    }
+                }
+            }
+            basis.push((pivot, row));
+            true
+        }
+        None => false,
+    }
+}
+
+/// Finding the loops of a traced skeleton. Loops sharing edges are reported
+/// as a minimal cycle basis, so a figure eight gives two loops. Loops are
+/// ordered by length.
+pub fn find_loops(graph: &SkeletonGraph) -> Vec<SkeletonLoop> {
+    let weights: Vec<f64> = graph.edges.iter().map(|e| e.length()).collect();
+
+    // Candidate cycles of Horton: the shortest paths from a node to both ends
+    // of an edge, closed by the edge
+    let mut candidates: Vec<(f64, Vec<Step>)> = vec![];
+    for v in 0..graph.nodes.len() {
+        let paths = shortest_paths(graph, &weights, v);
+
+        for (i, edge) in graph.edges.iter().enumerate() {
+            let (to_start, to_end) = match (&paths[edge.start], &paths[edge.end]) {
+                (Some(a), Some(b)) => (a, b),
+                _ => continue,
+            };
+            if to_start.iter().chain(to_end.iter()).any(|(e, _)| *e == i) {
+                continue;
+            }
+
+            // Both paths may only meet at v
+            let start_nodes = step_nodes(graph, to_start);
+            let end_nodes = step_nodes(graph, to_end);
+            if start_nodes.iter().any(|n| end_nodes.contains(n)) {
+                continue;
+            }
+
+            let mut steps = to_start.clone();
+            steps.push((i, true));
+            steps.extend(to_end.iter().rev().map(|(e, forward)| (*e, !forward)));
+
+            let weight = steps.iter().map(|(e, _)| weights[*e]).sum();
+            candidates.push((weight, steps));
+        }
+    }
+
+    candidates.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    let mut basis = vec![];
+    let mut loops = vec![];
+    for (_, steps) in candidates.iter() {
+        let mut row = vec![false; graph.edges.len()];
+        for (e, _) in steps.iter() {
+            row[*e] = true;
+        }
+        if add_to_basis(&mut basis, row) {
+            loops.push(build_loop(graph, steps));
+        }
+    }
+
+    loops
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_find_loops_char_b() {
+        let mut img = BinImage::try_from(PathBuf::from("./test_data/b_char_thinned.txt")).unwrap();
+        remove_redundant_pixels(&mut img);
+
+        let graph = trace_skeleton(&img);
+        let loops = find_loops(&graph);
+
+        assert_eq!(loops.len(), 2);
+        // One loop above and one below the middle bar on row 10
+        let mut centres: Vec<f64> = loops.iter().map(|l| l.centroid.1).collect();
+        centres.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert!(centres[0] < 10.0 && centres[1] > 10.0);
+        assert!(loops.iter().all(|l| l.edges.len() == 2 && l.area > 50.0));
+        for l in loops.iter() {
+            for pair in l.pixels.windows(2) {
+                assert!((pair[0].0 as isize - pair[1].0 as isize).abs() <= 1);
+                assert!((pair[0].1 as isize - pair[1].1 as isize).abs() <= 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_loops_ring() {
+        let img = BinImage::try_from(vec![
+            vec![false, true, true, true, false],
+            vec![true, false, false, false, true],
+            vec![true, false, false, false, true],
+            vec![false, true, true, true, false],
+        ])
+        .unwrap();
+
+        let loops = find_loops(&trace_skeleton(&img));
+
+        assert_eq!(loops.len(), 1);
+        assert_eq!(loops[0].pixels.len(), 10);
+        assert_eq!(loops[0].pixels[0], (1, 0));
+        assert_eq!(loops[0].area, 10.0);
+        assert_eq!(loops[0].centroid, (2.0, 1.5));
+    }
+
+    #[test]
+    fn test_find_loops_tree() {
+        let img = BinImage::try_from(vec![
+            vec![true, false, true],
+            vec![false, true, false],
+            vec![false, true, false],
+        ])
+        .unwrap();
+
+        assert!(find_loops(&trace_skeleton(&img)).is_empty());
+    }
+}
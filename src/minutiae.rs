@@ -0,0 +1,514 @@
+// Fingerprint minutiae of thinned ridge maps
+use crate::bin_image::BinImage;
+use crate::common::NEIGHBOR_OFFSETS;
+use crate::neighborhood::Neighborhood;
+use crate::skeleton::foreground_neighbors;
+#[cfg(test)]
+use std::f64::consts::PI;
+use std::io::{Error, ErrorKind, Write};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MinutiaKind {
+    /// A ridge pixel with one background to ridge transition around it
+    Ending,
+    /// A ridge pixel with three background to ridge transitions around it
+    Bifurcation,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Minutia {
+    pub x: usize,
+    pub y: usize,
+    pub kind: MinutiaKind,
+    /// Direction in radians between 0 and `2 * PI`, anticlockwise from the
+    /// positive x axis with the y axis pointing up. An ending points from the
+    /// ridge towards its end. A bifurcation points from the two closest
+    /// branches towards the third one, like the valley ending between them.
+    pub angle: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct MinutiaeOptions {
+    /// Number of ridge pixels followed to find the direction of a minutia
+    pub trace_length: usize,
+    /// Minutiae closer than this to the image border or to the background of
+    /// `mask` are removed
+    pub border: usize,
+    /// Fingerprint area with the same dimensions as the image. Without it the
+    /// whole image is the fingerprint.
+    pub mask: Option<BinImage>,
+    /// Ridges shorter than this between an ending and another minutia are
+    /// removed with both minutiae. 0 keeps all spurs.
+    pub min_spur_length: usize,
+    /// Ridges up to this length between two bifurcations are removed with
+    /// both bifurcations. 0 keeps all bridges.
+    pub max_bridge_length: usize,
+    /// Pairs of minutiae closer than this are both removed. 0 keeps all
+    /// pairs.
+    pub min_distance: f64,
+}
+
+impl Default for MinutiaeOptions {
+    // Ridges are around 9 pixels apart at 500 dpi
+    fn default() -> MinutiaeOptions {
+        MinutiaeOptions {
+            trace_length: 8,
+            border: 10,
+            mask: None,
+            min_spur_length: 10,
+            max_bridge_length: 10,
+            min_distance: 6.0,
+        }
+    }
+}
+
+fn transition_count(image: &BinImage, (x, y): (usize, usize)) -> usize {
+    image.get_neighbors(x, y).transition_count()
+}
+
+// First pixel of every branch leaving a pixel. Touching neighbors belong to
+// the same branch, which starts at the 4-neighbor if there is one.
+fn branches(n: Neighborhood, x: usize, y: usize) -> Vec<(usize, usize)> {
+    (0..8)
+        .filter(|i| n.get(*i) && !n.get(i + 7))
+        .map(|start| {
+            let run: Vec<usize> = (start..start + 8).take_while(|i| n.get(*i)).collect();
+            let i = run.iter().find(|i| *i % 2 == 0).unwrap_or(&run[0]) % 8;
+            let (dx, dy) = NEIGHBOR_OFFSETS[i];
+            ((x as isize + dx) as usize, (y as isize + dy) as usize)
+        })
+        .collect()
+}
+
+// Following a ridge from `from` through `first` for up to `length` pixels,
+// without going through `avoid`. Stops after the first pixel which is not on
+// a plain ridge, like a minutia.
+fn follow(
+    image: &BinImage,
+    from: (usize, usize),
+    first: (usize, usize),
+    length: usize,
+    avoid: &[(usize, usize)],
+) -> Vec<(usize, usize)> {
+    let mut path = vec![first];
+    let mut cur = first;
+
+    while path.len() < length && transition_count(image, cur) == 2 {
+        let candidates: Vec<(usize, usize)> = foreground_neighbors(image, cur.0, cur.1)
+            .into_iter()
+            .filter(|p| *p != from && !avoid.contains(p) && !path.contains(p))
+            .collect();
+
+        // Minutiae are not skipped by a diagonal step
+        let next = candidates
+            .iter()
+            .find(|p| transition_count(image, **p) != 2)
+            .or_else(|| candidates.iter().find(|p| p.0 == cur.0 || p.1 == cur.1))
+            .or_else(|| candidates.first());
+
+        match next {
+            Some(next) => {
+                cur = *next;
+                path.push(cur);
+            }
+            None => break,
+        }
+    }
+
+    path
+}
+
+// Unit vector from `from` to `to` with the y axis pointing up
+fn direction(from: (usize, usize), to: (usize, usize)) -> (f64, f64) {
+    let dx = to.0 as f64 - from.0 as f64;
+    let dy = from.1 as f64 - to.1 as f64;
+    let length = (dx * dx + dy * dy).sqrt();
+    (dx / length, dy / length)
+}
+
+fn angle((dx, dy): (f64, f64)) -> f64 {
+    dy.atan2(dx).rem_euclid(2.0 * std::f64::consts::PI)
+}
+
+// Direction of a minutia from the ends of the ridges leaving it
+fn minutia_angle(image: &BinImage, x: usize, y: usize, length: usize) -> f64 {
+    let starts = branches(image.get_neighbors(x, y), x, y);
+    let ends: Vec<(usize, usize)> = starts
+        .iter()
+        .map(|first| {
+            *follow(image, (x, y), *first, length.max(1), &starts)
+                .last()
+                .unwrap()
+        })
+        .collect();
+    let directions: Vec<(f64, f64)> = ends.iter().map(|end| direction((x, y), *end)).collect();
+
+    if directions.len() < 3 {
+        let (dx, dy) = directions[0];
+        return angle((-dx, -dy));
+    }
+
+    // The two branches with the smallest angle between them
+    let mut pairs = [(0, 1), (0, 2), (1, 2)];
+    let dot = |(a, b): (usize, usize)| {
+        directions[a].0 * directions[b].0 + directions[a].1 * directions[b].1
+    };
+    pairs.sort_by(|p, q| dot(*q).partial_cmp(&dot(*p)).unwrap());
+    let (a, b) = pairs[0];
+
+    angle((
+        -(directions[a].0 + directions[b].0),
+        -(directions[a].1 + directions[b].1),
+    ))
+}
+
+fn is_border(image: &BinImage, x: usize, y: usize, options: &MinutiaeOptions) -> bool {
+    let border = options.border;
+    if x < border
+        || y < border
+        || x + border >= image.get_width()
+        || y + border >= image.get_height()
+    {
+        return true;
+    }
+
+    match &options.mask {
+        Some(mask) => (y - border..=y + border)
+            .any(|my| (x - border..=x + border).any(|mx| !mask.get_value(mx, my).unwrap())),
+        None => false,
+    }
+}
+
+/// Finding the minutiae of a thinned ridge map. Ridges are the foreground and
+/// should be one pixel thick, see `remove_redundant_pixels`.
+///
+/// Endings and bifurcations are found by the number of background to ridge
+/// transitions around them. False minutiae are then removed: minutiae near
+/// the border of the fingerprint, short spurs and ridges, bridges between
+/// ridges, and close pairs, in this order. Minutiae are returned in raster
+/// order.
+pub fn extract_minutiae(
+    image: &BinImage,
+    options: &MinutiaeOptions,
+) -> Result<Vec<Minutia>, Error> {
+    if let Some(mask) = &options.mask {
+        if mask.get_width() != image.get_width() || mask.get_height() != image.get_height() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Mask has different dimensions",
+            ));
+        }
+    }
+
+    let mut minutiae = vec![];
+    for (x, y, n) in image.neighborhoods() {
+        let kind = match (n.centre(), n.transition_count()) {
+            (true, 1) => MinutiaKind::Ending,
+            (true, 3) => MinutiaKind::Bifurcation,
+            _ => continue,
+        };
+        let angle = minutia_angle(image, x, y, options.trace_length);
+        minutiae.push(Minutia { x, y, kind, angle });
+    }
+
+    let index = |p: (usize, usize)| minutiae.iter().position(|m| (m.x, m.y) == p);
+    let mut removed = vec![false; minutiae.len()];
+
+    for (i, m) in minutiae.iter().enumerate() {
+        if is_border(image, m.x, m.y, options) {
+            removed[i] = true;
+        }
+    }
+
+    // Spurs and short ridges start at an ending, bridges at a bifurcation
+    for (i, m) in minutiae.iter().enumerate() {
+        let length = match m.kind {
+            MinutiaKind::Ending if options.min_spur_length > 0 => options.min_spur_length - 1,
+            MinutiaKind::Bifurcation => options.max_bridge_length,
+            _ => continue,
+        };
+        let starts = branches(image.get_neighbors(m.x, m.y), m.x, m.y);
+
+        for first in starts.iter() {
+            let path = follow(image, (m.x, m.y), *first, length, &starts);
+            let other = match index(*path.last().unwrap()) {
+                Some(other) if other != i && path.len() <= length => other,
+                _ => continue,
+            };
+            if m.kind == MinutiaKind::Ending || minutiae[other].kind == MinutiaKind::Bifurcation {
+                removed[i] = true;
+                removed[other] = true;
+            }
+        }
+    }
+
+    let kept: Vec<usize> = (0..minutiae.len()).filter(|i| !removed[*i]).collect();
+    for (k, i) in kept.iter().enumerate() {
+        for j in kept.iter().skip(k + 1) {
+            let (a, b) = (&minutiae[*i], &minutiae[*j]);
+            let distance =
+                ((a.x as f64 - b.x as f64).powi(2) + (a.y as f64 - b.y as f64).powi(2)).sqrt();
+            if distance < options.min_distance {
+                removed[*i] = true;
+                removed[*j] = true;
+            }
+        }
+    }
+
+    Ok(minutiae
+        .into_iter()
+        .zip(removed)
+        .filter(|(_, removed)| !removed)
+        .map(|(m, _)| m)
+        .collect())
+}
+
+/// Writing minutiae as a finger minutiae record laid out like ISO/IEC
+/// 19794-2:2005, with a single finger view of unknown position and quality.
+/// `resolution` is in pixels per centimetre. Angles are stored in steps of
+/// 1.40625 degrees.
+pub fn write_minutiae_record<W: Write>(
+    writer: &mut W,
+    width: usize,
+    height: usize,
+    resolution: u16,
+    minutiae: &[Minutia],
+) -> Result<(), Error> {
+    if minutiae.len() > 255 {
+        return Err(Error::new(ErrorKind::InvalidInput, "Too many minutiae"));
+    }
+    if width > 0xFFFF || height > 0xFFFF || minutiae.iter().any(|m| m.x > 0x3FFF || m.y > 0x3FFF) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Coordinates are too large",
+        ));
+    }
+
+    // Record header, finger view header, minutiae and extended data length
+    let length = 24 + 4 + minutiae.len() * 6 + 2;
+
+    let mut record: Vec<u8> = vec![];
+    record.extend(b"FMR\0 20\0");
+    record.extend((length as u32).to_be_bytes());
+    record.extend([0, 0]);
+    record.extend((width as u16).to_be_bytes());
+    record.extend((height as u16).to_be_bytes());
+    record.extend(resolution.to_be_bytes());
+    record.extend(resolution.to_be_bytes());
+    record.extend([1, 0]);
+
+    record.extend([0, 0, 0, minutiae.len() as u8]);
+    for m in minutiae.iter() {
+        let kind: u16 = match m.kind {
+            MinutiaKind::Ending => 1,
+            MinutiaKind::Bifurcation => 2,
+        };
+        let angle = (m.angle.to_degrees() / 1.40625).round() as u32 % 256;
+        record.extend((kind << 14 | m.x as u16).to_be_bytes());
+        record.extend((m.y as u16).to_be_bytes());
+        record.extend([angle as u8, 0]);
+    }
+    record.extend([0, 0]);
+
+    writer.write_all(&record)
+}
+
+/// Encoding minutiae as a finger minutiae record, see
+/// `write_minutiae_record`
+pub fn to_minutiae_record(
+    width: usize,
+    height: usize,
+    resolution: u16,
+    minutiae: &[Minutia],
+) -> Result<Vec<u8>, Error> {
+    let mut buffer = vec![];
+    write_minutiae_record(&mut buffer, width, height, resolution, minutiae)?;
+
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    type Line = ((usize, usize), (usize, usize));
+
+    fn draw(width: usize, height: usize, lines: &[Line]) -> BinImage {
+        let mut image = BinImage::new(width, height, false);
+        for ((x0, y0), (x1, y1)) in lines.iter() {
+            let steps = (*x1 as isize - *x0 as isize)
+                .abs()
+                .max((*y1 as isize - *y0 as isize).abs());
+            for s in 0..=steps {
+                let x = *x0 as isize + (*x1 as isize - *x0 as isize) * s / steps.max(1);
+                let y = *y0 as isize + (*y1 as isize - *y0 as isize) * s / steps.max(1);
+                image.set_value(x as usize, y as usize, true).unwrap();
+            }
+        }
+        image
+    }
+
+    fn unfiltered() -> MinutiaeOptions {
+        MinutiaeOptions {
+            border: 0,
+            min_spur_length: 0,
+            max_bridge_length: 0,
+            min_distance: 0.0,
+            ..MinutiaeOptions::default()
+        }
+    }
+
+    fn kinds(minutiae: &[Minutia]) -> Vec<(usize, usize, MinutiaKind)> {
+        minutiae.iter().map(|m| (m.x, m.y, m.kind)).collect()
+    }
+
+    #[test]
+    fn test_detection() {
+        // A stem forking into two branches
+        let image = draw(
+            30,
+            30,
+            &[
+                ((3, 15), (15, 15)),
+                ((15, 15), (27, 3)),
+                ((15, 15), (27, 27)),
+            ],
+        );
+
+        let minutiae = extract_minutiae(&image, &unfiltered()).unwrap();
+
+        assert_eq!(
+            kinds(&minutiae),
+            vec![
+                (27, 3, MinutiaKind::Ending),
+                (3, 15, MinutiaKind::Ending),
+                (15, 15, MinutiaKind::Bifurcation),
+                (27, 27, MinutiaKind::Ending),
+            ]
+        );
+        let angles: Vec<f64> = minutiae.iter().map(|m| m.angle).collect();
+        for (angle, expected) in angles.iter().zip([PI / 4.0, PI, PI, 7.0 * PI / 4.0]) {
+            assert!((angle - expected).abs() < 1e-9, "{} {}", angle, expected);
+        }
+
+        // The endings are near the border
+        assert_eq!(
+            kinds(&extract_minutiae(&image, &MinutiaeOptions::default()).unwrap()),
+            vec![(15, 15, MinutiaKind::Bifurcation)]
+        );
+    }
+
+    #[test]
+    fn test_spurs_and_bridges() {
+        // A ridge with a short spur
+        let image = draw(40, 20, &[((2, 10), (37, 10)), ((20, 11), (20, 13))]);
+        assert_eq!(extract_minutiae(&image, &unfiltered()).unwrap().len(), 4);
+
+        let options = MinutiaeOptions {
+            min_spur_length: 10,
+            ..unfiltered()
+        };
+        assert_eq!(
+            kinds(&extract_minutiae(&image, &options).unwrap()),
+            vec![(2, 10, MinutiaKind::Ending), (37, 10, MinutiaKind::Ending)]
+        );
+
+        // Two ridges joined by a bridge
+        let image = draw(
+            40,
+            20,
+            &[((2, 5), (37, 5)), ((2, 15), (37, 15)), ((20, 6), (20, 14))],
+        );
+        assert_eq!(extract_minutiae(&image, &unfiltered()).unwrap().len(), 6);
+
+        let options = MinutiaeOptions {
+            max_bridge_length: 12,
+            ..unfiltered()
+        };
+        let minutiae = extract_minutiae(&image, &options).unwrap();
+        assert_eq!(minutiae.len(), 4);
+        assert!(minutiae.iter().all(|m| m.kind == MinutiaKind::Ending));
+
+        // A short ridge is removed, a long one is kept
+        let image = draw(40, 20, &[((2, 5), (37, 5)), ((10, 15), (14, 15))]);
+        let options = MinutiaeOptions {
+            min_spur_length: 10,
+            ..unfiltered()
+        };
+        assert_eq!(
+            kinds(&extract_minutiae(&image, &options).unwrap()),
+            vec![(2, 5, MinutiaKind::Ending), (37, 5, MinutiaKind::Ending)]
+        );
+    }
+
+    #[test]
+    fn test_border_and_close_pairs() {
+        // A broken ridge
+        let image = draw(40, 20, &[((2, 10), (17, 10)), ((21, 10), (37, 10))]);
+
+        let options = MinutiaeOptions {
+            min_distance: 6.0,
+            ..unfiltered()
+        };
+        assert_eq!(
+            kinds(&extract_minutiae(&image, &options).unwrap()),
+            vec![(2, 10, MinutiaKind::Ending), (37, 10, MinutiaKind::Ending)]
+        );
+
+        // Only the left half is in the fingerprint
+        let mut mask = BinImage::new(40, 20, false);
+        for (x, _, val) in mask.iter_mut() {
+            *val = x < 21;
+        }
+        let options = MinutiaeOptions {
+            border: 3,
+            mask: Some(mask),
+            ..unfiltered()
+        };
+        assert_eq!(
+            kinds(&extract_minutiae(&image, &options).unwrap()),
+            vec![(17, 10, MinutiaKind::Ending)]
+        );
+
+        let options = MinutiaeOptions {
+            mask: Some(BinImage::new(4, 4, true)),
+            ..unfiltered()
+        };
+        assert!(extract_minutiae(&image, &options).is_err());
+    }
+
+    #[test]
+    fn test_minutiae_record() {
+        let minutiae = vec![
+            Minutia {
+                x: 300,
+                y: 5,
+                kind: MinutiaKind::Ending,
+                angle: PI,
+            },
+            Minutia {
+                x: 7,
+                y: 400,
+                kind: MinutiaKind::Bifurcation,
+                angle: 0.0,
+            },
+        ];
+
+        let record = to_minutiae_record(500, 600, 197, &minutiae).unwrap();
+
+        assert_eq!(record.len(), 42);
+        assert_eq!(&record[0..8], b"FMR\0 20\0");
+        assert_eq!(&record[8..12], &[0, 0, 0, 42]);
+        assert_eq!(&record[14..22], &[1, 244, 2, 88, 0, 197, 0, 197]);
+        assert_eq!(record[27], 2);
+        assert_eq!(&record[28..34], &[0x41, 0x2C, 0, 5, 128, 0]);
+        assert_eq!(&record[34..40], &[0x80, 7, 1, 144, 0, 0]);
+        assert_eq!(&record[40..42], &[0, 0]);
+
+        let too_far = vec![Minutia {
+            x: 0x4000,
+            ..minutiae[0].clone()
+        }];
+        assert!(to_minutiae_record(500, 600, 197, &too_far).is_err());
+    }
+}
@@ -0,0 +1,261 @@
+// The 3 by 3 neighborhood of a pixel
+use crate::connectivity::{
+    HILDITCH_TABLE, NEIGHBOR_COUNT_TABLE, SIMPLE_POINT_TABLE, TRANSITION_TABLE, YOKOI_4_TABLE,
+    YOKOI_8_TABLE,
+};
+
+/// A pixel and its eight neighbors, named like in the paper of Zhang and
+/// Suen:
+///
+/// ```text
+/// P9 P2 P3
+/// P8 P1 P4
+/// P7 P6 P5
+/// ```
+///
+/// The neighbors P2, P3, .. P9 are stored as the bits 0 to 7 of a mask, the
+/// same mask as in `connectivity`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Neighborhood {
+    centre: bool,
+    mask: u8,
+}
+
+impl Neighborhood {
+    pub fn new(centre: bool, mask: u8) -> Neighborhood {
+        Neighborhood { centre, mask }
+    }
+
+    /// Creating a neighborhood from the values of P2, P3, .. P9
+    pub fn from_array(centre: bool, neighbors: [bool; 8]) -> Neighborhood {
+        let mask = neighbors
+            .iter()
+            .enumerate()
+            .fold(0, |mask, (i, p)| if *p { mask | (1 << i) } else { mask });
+
+        Neighborhood { centre, mask }
+    }
+
+    /// Values of P2, P3, .. P9
+    pub fn to_array(&self) -> [bool; 8] {
+        let mut neighbors = [false; 8];
+        for (i, p) in neighbors.iter_mut().enumerate() {
+            *p = self.get(i);
+        }
+        neighbors
+    }
+
+    /// Neighbor `i` in the order P2, P3, .. P9, so 0 is P2
+    pub fn get(&self, i: usize) -> bool {
+        self.mask & (1 << (i % 8)) != 0
+    }
+
+    pub fn centre(&self) -> bool {
+        self.centre
+    }
+
+    pub fn mask(&self) -> u8 {
+        self.mask
+    }
+
+    /// Index of the neighbors in a 256 entry table, such as the tables of
+    /// `connectivity`. The centre is not a part of the index.
+    pub fn index(&self) -> usize {
+        self.mask as usize
+    }
+
+    pub fn p1(&self) -> bool {
+        self.centre
+    }
+
+    pub fn p2(&self) -> bool {
+        self.get(0)
+    }
+
+    pub fn p3(&self) -> bool {
+        self.get(1)
+    }
+
+    pub fn p4(&self) -> bool {
+        self.get(2)
+    }
+
+    pub fn p5(&self) -> bool {
+        self.get(3)
+    }
+
+    pub fn p6(&self) -> bool {
+        self.get(4)
+    }
+
+    pub fn p7(&self) -> bool {
+        self.get(5)
+    }
+
+    pub fn p8(&self) -> bool {
+        self.get(6)
+    }
+
+    pub fn p9(&self) -> bool {
+        self.get(7)
+    }
+
+    pub fn north(&self) -> bool {
+        self.p2()
+    }
+
+    pub fn north_east(&self) -> bool {
+        self.p3()
+    }
+
+    pub fn east(&self) -> bool {
+        self.p4()
+    }
+
+    pub fn south_east(&self) -> bool {
+        self.p5()
+    }
+
+    pub fn south(&self) -> bool {
+        self.p6()
+    }
+
+    pub fn south_west(&self) -> bool {
+        self.p7()
+    }
+
+    pub fn west(&self) -> bool {
+        self.p8()
+    }
+
+    pub fn north_west(&self) -> bool {
+        self.p9()
+    }
+
+    /// Rotating clockwise by `quarter_turns` times 90 degrees, so the north
+    /// neighbor becomes the east neighbor with one quarter turn
+    pub fn rotate(&self, quarter_turns: usize) -> Neighborhood {
+        Neighborhood {
+            centre: self.centre,
+            mask: self.mask.rotate_left((quarter_turns % 4) as u32 * 2),
+        }
+    }
+
+    /// Mirroring left to right, swapping the east and west neighbors
+    pub fn reflect_horizontal(&self) -> Neighborhood {
+        self.remap(|i| (8 - i) % 8)
+    }
+
+    /// Mirroring top to bottom, swapping the north and south neighbors
+    pub fn reflect_vertical(&self) -> Neighborhood {
+        self.remap(|i| (12 - i) % 8)
+    }
+
+    // Neighbor `i` of the new neighborhood is neighbor `from(i)` of this one
+    fn remap<F: Fn(usize) -> usize>(&self, from: F) -> Neighborhood {
+        Neighborhood {
+            centre: self.centre,
+            mask: (0..8).fold(0, |mask, i| {
+                if self.get(from(i)) {
+                    mask | (1 << i)
+                } else {
+                    mask
+                }
+            }),
+        }
+    }
+
+    /// Number of foreground neighbors, `B(P)`
+    pub fn count(&self) -> usize {
+        NEIGHBOR_COUNT_TABLE[self.index()] as usize
+    }
+
+    /// `A(P)`, see the function `transition_count`
+    pub fn transition_count(&self) -> usize {
+        TRANSITION_TABLE[self.index()] as usize
+    }
+
+    /// See `hilditch_crossing_number`
+    pub fn hilditch_crossing_number(&self) -> usize {
+        HILDITCH_TABLE[self.index()] as usize
+    }
+
+    pub fn yokoi_4(&self) -> usize {
+        YOKOI_4_TABLE[self.index()] as usize
+    }
+
+    pub fn yokoi_8(&self) -> usize {
+        YOKOI_8_TABLE[self.index()] as usize
+    }
+
+    /// Whether the centre is a foreground pixel which can be deleted without
+    /// changing the topology, see `is_simple_point`
+    pub fn is_simple(&self) -> bool {
+        self.centre && SIMPLE_POINT_TABLE[self.index()]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_accessors() {
+        let n =
+            Neighborhood::from_array(true, [true, false, true, false, false, false, true, true]);
+
+        assert!(n.p1() && n.centre());
+        assert!(n.north() && n.east() && n.west() && n.north_west());
+        assert!(!n.north_east() && !n.south_east() && !n.south() && !n.south_west());
+        assert_eq!(n.mask(), 0b1100_0101);
+        assert_eq!(n.index(), 0b1100_0101);
+        assert_eq!(
+            n.to_array(),
+            [true, false, true, false, false, false, true, true]
+        );
+        assert_eq!(Neighborhood::new(true, n.mask()), n);
+    }
+
+    #[test]
+    fn test_connectivity() {
+        let n = Neighborhood::from_array(true, [true, true, false, false, true, false, true, true]);
+        assert_eq!(n.count(), 5);
+        assert_eq!(n.transition_count(), 2);
+
+        assert_eq!(Neighborhood::new(true, 255).count(), 8);
+        assert_eq!(Neighborhood::new(true, 255).transition_count(), 0);
+        assert_eq!(Neighborhood::new(true, 0).transition_count(), 0);
+
+        // An end point is simple, but not when the centre is background
+        let end = Neighborhood::from_array(
+            true,
+            [false, false, true, false, false, false, false, false],
+        );
+        assert!(end.is_simple());
+        assert!(!Neighborhood::new(false, end.mask()).is_simple());
+    }
+
+    #[test]
+    fn test_rotation_and_reflection() {
+        let north = Neighborhood::from_array(
+            true,
+            [true, false, false, false, false, false, false, false],
+        );
+        assert!(north.rotate(1).east());
+        assert!(north.rotate(2).south());
+        assert!(north.rotate(3).west());
+        assert_eq!(north.rotate(4), north);
+
+        let north_east = Neighborhood::new(false, 0b10);
+        assert!(north_east.rotate(1).south_east());
+        assert!(north_east.reflect_horizontal().north_west());
+        assert!(north_east.reflect_vertical().south_east());
+        assert!(north.reflect_horizontal().north());
+        assert!(north.reflect_vertical().south());
+        assert_eq!(
+            north_east.reflect_horizontal().reflect_horizontal(),
+            north_east
+        );
+        assert!(!north_east.rotate(1).centre());
+    }
+}
@@ -0,0 +1,331 @@
+// Options to change the behaviour of the thinning algorithm
+use crate::bin_image::BinImage;
+#[cfg(test)]
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind};
+
+/// Part of the image to thin. Only pixels inside the region are evaluated
+/// and deleted. Pixels outside the region are kept as they are, but they are
+/// still used as neighbors of the pixels on the region border.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Region {
+    /// A rectangle with the top left corner at `x` and `y`. Parts outside of
+    /// the image are ignored.
+    Rect {
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    },
+    /// A mask with the same dimensions as the image. `true` pixels are inside
+    /// the region.
+    Mask(BinImage),
+}
+
+impl Region {
+    // Bounding rectangle of the region clipped to the image as
+    // `(x0, y0, x1, y1)` with exclusive ends
+    pub(crate) fn bounds(&self, image: &BinImage) -> Result<(usize, usize, usize, usize), Error> {
+        let width = image.get_width();
+        let height = image.get_height();
+
+        match self {
+            Region::Rect {
+                x,
+                y,
+                width: w,
+                height: h,
+            } => Ok((
+                (*x).min(width),
+                (*y).min(height),
+                x.saturating_add(*w).min(width),
+                y.saturating_add(*h).min(height),
+            )),
+            Region::Mask(mask) => {
+                if mask.get_width() != width || mask.get_height() != height {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        "Region mask has different dimensions",
+                    ));
+                }
+
+                let mut bounds = (width, height, 0, 0);
+                for (y, row) in mask.get_pixels().iter().enumerate() {
+                    for (x, val) in row.iter().enumerate() {
+                        if *val {
+                            bounds.0 = bounds.0.min(x);
+                            bounds.1 = bounds.1.min(y);
+                            bounds.2 = bounds.2.max(x + 1);
+                            bounds.3 = bounds.3.max(y + 1);
+                        }
+                    }
+                }
+
+                if bounds.0 >= bounds.2 {
+                    Ok((0, 0, 0, 0))
+                } else {
+                    Ok(bounds)
+                }
+            }
+        }
+    }
+
+    // Whether a pixel is a part of the region
+    pub(crate) fn contains(&self, x: usize, y: usize) -> bool {
+        match self {
+            Region::Rect {
+                x: rx,
+                y: ry,
+                width,
+                height,
+            } => x >= *rx && y >= *ry && x - rx < *width && y - ry < *height,
+            Region::Mask(mask) => mask.get_value(x, y).unwrap_or(false),
+        }
+    }
+}
+
+/// Options for `imgthin_with_options`. The default options give the same
+/// result as `imgthin`, except for `preserve_small_shapes`.
+#[derive(Clone, Debug)]
+pub struct ThinOptions {
+    /// Removing the remaining redundant pixels after thinning, so the
+    /// skeleton is always one pixel thick
+    pub unit_thickness: bool,
+    /// Thinning only a part of the image. The whole image is thinned when
+    /// this is `None`.
+    pub region: Option<Region>,
+    /// Anchor pixels which are never deleted, with the same dimensions as the
+    /// image. Anchors stay connected to the rest of their component, as the
+    /// pixels around them are only deleted when that keeps the connectivity.
+    /// `false` pixels of the mask have no effect.
+    pub anchors: Option<BinImage>,
+    /// Keeping lone 2 by 2 blocks and diagonal lines two pixels thick, which
+    /// the conditions of Zhang and Suen delete entirely. A block is thinned
+    /// to its top left pixel, and a diagonal line to one pixel on every
+    /// step. This is on by default, but `imgthin` keeps the original
+    /// conditions. Only used by the Zhang-Suen algorithm.
+    pub preserve_small_shapes: bool,
+    /// Deleting pixels as soon as they are visited in raster order, so later
+    /// pixels of the same sub-iteration see the deletions. This was the
+    /// behaviour of the improved algorithm before it was made parallel like
+    /// in the paper. The result depends on the scan direction. The parallel
+    /// variant keeps the top left pixel of a component when it would delete
+    /// all of its pixels at once. Zhang-Suen ignores this option.
+    pub sequential: bool,
+    /// Stopping after this many iterations of two sub-iterations, even when
+    /// thinning has not converged. `imgthin_centred` has no iterations and
+    /// fails when this is set.
+    pub max_iterations: Option<usize>,
+}
+
+impl Default for ThinOptions {
+    fn default() -> ThinOptions {
+        ThinOptions {
+            unit_thickness: false,
+            region: None,
+            anchors: None,
+            preserve_small_shapes: true,
+            sequential: false,
+            max_iterations: None,
+        }
+    }
+}
+
+impl ThinOptions {
+    // Bounds of the pixels to evaluate as `(x0, y0, x1, y1)`. Fails when a
+    // mask does not fit the image.
+    pub(crate) fn region_bounds(
+        &self,
+        image: &BinImage,
+    ) -> Result<(usize, usize, usize, usize), Error> {
+        if let Some(anchors) = &self.anchors {
+            if anchors.get_width() != image.get_width()
+                || anchors.get_height() != image.get_height()
+            {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "Anchor mask has different dimensions",
+                ));
+            }
+        }
+
+        match &self.region {
+            Some(region) => region.bounds(image),
+            None => Ok((0, 0, image.get_width(), image.get_height())),
+        }
+    }
+
+    // Whether a pixel can be evaluated and deleted
+    pub(crate) fn in_region(&self, x: usize, y: usize) -> bool {
+        match &self.region {
+            Some(region) => region.contains(x, y),
+            None => true,
+        }
+    }
+
+    pub(crate) fn is_anchored(&self, x: usize, y: usize) -> bool {
+        match &self.anchors {
+            Some(anchors) => anchors.get_value(x, y).unwrap_or(false),
+            None => false,
+        }
+    }
+
+    // Whether a pixel can be deleted, when it is in the region and not an
+    // anchor
+    pub(crate) fn can_delete(&self, x: usize, y: usize) -> bool {
+        self.in_region(x, y) && !self.is_anchored(x, y)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::topology::{label_components, verify_topology};
+
+    #[test]
+    fn test_region_bounds() {
+        let image = BinImage::new(10, 8, false);
+
+        let rect = Region::Rect {
+            x: 2,
+            y: 3,
+            width: 20,
+            height: 2,
+        };
+        assert_eq!(rect.bounds(&image).unwrap(), (2, 3, 10, 5));
+        assert!(rect.contains(2, 4));
+        assert!(!rect.contains(2, 5));
+        assert!(!rect.contains(1, 3));
+
+        let mut mask = BinImage::new(10, 8, false);
+        mask.set_value(4, 1, true).unwrap();
+        mask.set_value(6, 5, true).unwrap();
+        let region = Region::Mask(mask);
+        assert_eq!(region.bounds(&image).unwrap(), (4, 1, 7, 6));
+        assert!(region.contains(4, 1));
+        assert!(!region.contains(5, 1));
+
+        let empty = Region::Mask(BinImage::new(10, 8, false));
+        assert_eq!(empty.bounds(&image).unwrap(), (0, 0, 0, 0));
+
+        let invalid = Region::Mask(BinImage::new(3, 3, false));
+        assert!(invalid.bounds(&image).is_err());
+    }
+
+    #[test]
+    fn test_anchors() {
+        let image = BinImage::new(4, 3, false);
+        let mut anchors = BinImage::new(4, 3, false);
+        anchors.set_value(1, 2, true).unwrap();
+
+        let options = ThinOptions {
+            region: Some(Region::Rect {
+                x: 0,
+                y: 0,
+                width: 2,
+                height: 3,
+            }),
+            anchors: Some(anchors),
+            ..ThinOptions::default()
+        };
+        assert!(options.region_bounds(&image).is_ok());
+        assert!(options.can_delete(0, 2));
+        assert!(!options.can_delete(1, 2));
+        assert!(!options.can_delete(2, 2));
+
+        let invalid = ThinOptions {
+            anchors: Some(BinImage::new(3, 3, false)),
+            ..ThinOptions::default()
+        };
+        assert!(invalid.region_bounds(&image).is_err());
+    }
+
+    // A bar 5 pixels thick
+    fn bar(top: usize) -> Vec<Vec<bool>> {
+        let mut pixels = vec![vec![false; 20]; 9];
+        for row in pixels.iter_mut().skip(top).take(5) {
+            for val in row.iter_mut().skip(1).take(18) {
+                *val = true;
+            }
+        }
+        pixels
+    }
+
+    #[test]
+    fn test_thin_region() {
+        let pixels = bar(2);
+        let img = BinImage::try_from(pixels.clone()).unwrap();
+
+        let options = ThinOptions {
+            unit_thickness: true,
+            region: Some(Region::Rect {
+                x: 0,
+                y: 0,
+                width: 10,
+                height: 9,
+            }),
+            ..ThinOptions::default()
+        };
+        let thinned = crate::imgthin_with_options(pixels.clone(), &options).unwrap();
+
+        for row in 0..9 {
+            assert_eq!(thinned[row][10..], pixels[row][10..]);
+        }
+        assert!(thinned[2][5..10] != pixels[2][5..10]);
+        let thinned_img = BinImage::try_from(thinned.clone()).unwrap();
+        assert!(verify_topology(&img, &thinned_img).unwrap().is_preserved());
+
+        // The same region as a mask
+        let mut mask = BinImage::new(20, 9, false);
+        for y in 0..9 {
+            for x in 0..10 {
+                mask.set_value(x, y, true).unwrap();
+            }
+        }
+        let options = ThinOptions {
+            unit_thickness: true,
+            region: Some(Region::Mask(mask)),
+            ..ThinOptions::default()
+        };
+        assert_eq!(
+            crate::imgthin_with_options(pixels, &options).unwrap(),
+            thinned
+        );
+    }
+
+    #[test]
+    fn test_thin_anchors() {
+        // The bar and a 2 by 2 dot above it
+        let mut pixels = bar(3);
+        for row in pixels.iter_mut().take(2) {
+            row[9] = true;
+            row[10] = true;
+        }
+        let img = BinImage::try_from(pixels.clone()).unwrap();
+
+        let mut anchors = BinImage::new(20, 9, false);
+        anchors.set_value(9, 0, true).unwrap();
+        anchors.set_value(1, 3, true).unwrap();
+        anchors.set_value(18, 7, true).unwrap();
+        let options = ThinOptions {
+            unit_thickness: true,
+            anchors: Some(anchors),
+            ..ThinOptions::default()
+        };
+        let thinned = crate::imgthin_with_options(pixels, &options).unwrap();
+
+        assert!(thinned[0][9]);
+        assert!(thinned[3][1]);
+        assert!(thinned[7][18]);
+        let thinned_img = BinImage::try_from(thinned).unwrap();
+        assert!(verify_topology(&img, &thinned_img).unwrap().is_preserved());
+
+        // The anchored corners stay connected to each other through the
+        // skeleton of the bar, apart from the dot
+        let labels = label_components(&thinned_img, true, true);
+        assert_eq!(labels.seeds.len(), 2);
+        assert_eq!(labels.labels[3][1], labels.labels[7][18]);
+        assert_eq!(labels.labels[3][1], labels.labels[5][9]);
+        assert_ne!(labels.labels[0][9], labels.labels[3][1]);
+    }
+}
@@ -0,0 +1,186 @@
+// Order in which thinning deleted the pixels of an image
+use crate::bin_image::BinImage;
+use crate::common::thin;
+use crate::options::ThinOptions;
+#[cfg(test)]
+use crate::report::imgthin_with_report;
+#[cfg(test)]
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind};
+#[cfg(test)]
+use std::path::PathBuf;
+
+/// The sub-iteration in which every pixel was deleted, counted from 1.
+/// Background pixels of the original image are 0 and pixels of the skeleton
+/// are `PeelOrder::KEPT`, so the values grow from the border of a shape to
+/// its skeleton like a distance transform.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeelOrder {
+    order: Vec<Vec<u32>>,
+}
+
+impl PeelOrder {
+    /// Value of the pixels which were not deleted
+    pub const KEPT: u32 = u32::MAX;
+
+    // Every foreground pixel kept until it is deleted
+    pub(crate) fn new(pixels: &[Vec<bool>]) -> PeelOrder {
+        PeelOrder {
+            order: pixels
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|val| if *val { PeelOrder::KEPT } else { 0 })
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    pub(crate) fn set_deleted(&mut self, x: usize, y: usize, order: u32) {
+        self.order[y][x] = order;
+    }
+
+    // Pixels deleted after thinning, which are not foreground any more but
+    // still kept
+    pub(crate) fn set_removed(&mut self, thinned: &[Vec<bool>], order: u32) {
+        for (row, thinned_row) in self.order.iter_mut().zip(thinned.iter()) {
+            for (val, thinned) in row.iter_mut().zip(thinned_row.iter()) {
+                if *val == PeelOrder::KEPT && !thinned {
+                    *val = order;
+                }
+            }
+        }
+    }
+
+    pub fn get_width(&self) -> usize {
+        match self.order.first() {
+            Some(row) => row.len(),
+            None => 0,
+        }
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn get_value(&self, x: usize, y: usize) -> Result<u32, Error> {
+        match self.order.get(y) {
+            Some(row) => match row.get(x) {
+                Some(val) => Ok(*val),
+                None => Err(Error::new(ErrorKind::InvalidInput, "Invalid X coordinate")),
+            },
+            None => Err(Error::new(ErrorKind::InvalidInput, "Invalid Y coordinate")),
+        }
+    }
+
+    pub fn get_order(&self) -> &Vec<Vec<u32>> {
+        &self.order
+    }
+
+    /// The order as 16 bit values. `KEPT` becomes `u16::MAX`, and later
+    /// sub-iterations than `u16::MAX - 1` are clamped to it.
+    pub fn to_u16(&self) -> Vec<Vec<u16>> {
+        self.order
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|val| match *val {
+                        PeelOrder::KEPT => u16::MAX,
+                        val => val.min(u16::MAX as u32 - 1) as u16,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Pixels deleted in the sub-iteration `order` or later, and the pixels
+    /// of the skeleton. This thickens the skeleton back to the image before
+    /// the sub-iteration `order`, so 1 gives the original image.
+    pub fn threshold(&self, order: u32) -> BinImage {
+        let mut image = BinImage::new(self.get_width(), self.get_height(), false);
+        for (x, y, val) in image.iter_mut() {
+            let peeled = self.order[y][x];
+            *val = peeled != 0 && peeled >= order;
+        }
+        image
+    }
+}
+
+/// Thinning like `imgthin_with_options`, and returning the sub-iteration in
+/// which every pixel was deleted. Pixels removed by `unit_thickness` are
+/// given the number after the last sub-iteration.
+pub fn imgthin_with_peel_order(
+    pixels: Vec<Vec<bool>>,
+    options: &ThinOptions,
+) -> Result<(Vec<Vec<bool>>, PeelOrder), Error> {
+    let mut peel = PeelOrder::new(&pixels);
+
+    let (thinned, sub_iterations) = thin(pixels, options, &crate::CONDITIONS, None, |s, x, y| {
+        peel.set_deleted(x, y, s as u32 + 1)
+    })?;
+    peel.set_removed(&thinned, sub_iterations as u32 + 1);
+
+    Ok((thinned, peel))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_peel_order() {
+        let mut peel = PeelOrder::new(&[vec![false, true, true], vec![true, true, true]]);
+        peel.set_deleted(1, 0, 1);
+        peel.set_deleted(0, 1, 70000);
+        peel.set_removed(&[vec![false, false, true], vec![false, true, false]], 3);
+
+        assert_eq!(
+            peel.get_order(),
+            &vec![vec![0, 1, PeelOrder::KEPT], vec![70000, PeelOrder::KEPT, 3]]
+        );
+        assert_eq!(
+            peel.to_u16(),
+            vec![vec![0, 1, u16::MAX], vec![u16::MAX - 1, u16::MAX, 3]]
+        );
+        assert_eq!(peel.threshold(1).foreground().count(), 5);
+        assert_eq!(
+            peel.threshold(3).foreground().collect::<Vec<_>>(),
+            vec![(2, 0), (0, 1), (1, 1), (2, 1)]
+        );
+        assert_eq!(peel.threshold(PeelOrder::KEPT).foreground().count(), 2);
+        assert!(peel.get_value(3, 0).is_err());
+    }
+
+    #[test]
+    fn test_imgthin_peel_order() {
+        let img = BinImage::try_from(PathBuf::from("./test_data/b_char.txt")).unwrap();
+
+        let (thinned, peel) =
+            imgthin_with_peel_order(img.get_pixels().to_vec(), &crate::imgthin_options()).unwrap();
+        let (_, report) =
+            imgthin_with_report(img.get_pixels().to_vec(), &crate::imgthin_options()).unwrap();
+
+        let thinned = BinImage::try_from(thinned).unwrap();
+        crate::assert_bin_image_eq!(peel.threshold(PeelOrder::KEPT), thinned);
+        crate::assert_bin_image_eq!(peel.threshold(1), img);
+        for (s, deleted) in report.deleted.iter().enumerate() {
+            let order = s as u32 + 1;
+            let count = peel.get_order().iter().flatten().filter(|o| **o == order);
+            assert_eq!(count.count(), *deleted);
+        }
+
+        // Pixels removed for unit thickness come last
+        let options = ThinOptions {
+            unit_thickness: true,
+            ..crate::imgthin_options()
+        };
+        let (thinned, peel) = imgthin_with_peel_order(img.get_pixels().to_vec(), &options).unwrap();
+        let last = report.sub_iterations as u32 + 1;
+        let removed = peel.get_order().iter().flatten().filter(|o| **o == last);
+        assert_eq!(
+            removed.count(),
+            report.final_foreground - BinImage::try_from(thinned).unwrap().foreground().count()
+        );
+    }
+}
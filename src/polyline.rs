@@ -0,0 +1,225 @@
+// Converting traced skeletons into simplified polylines
+#[cfg(test)]
+use crate::bin_image::BinImage;
+#[cfg(test)]
+use crate::skeleton::trace_skeleton;
+use crate::skeleton::SkeletonGraph;
+#[cfg(test)]
+use std::convert::TryFrom;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Simplification {
+    /// Keeping every pixel of the path
+    None,
+    /// Douglas-Peucker with the maximum allowed distance in pixels
+    DouglasPeucker(f64),
+    /// Visvalingam-Whyatt with the minimum allowed triangle area in pixels
+    Visvalingam(f64),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polyline {
+    /// Index of the first node in `SkeletonGraph::nodes`
+    pub start: usize,
+    /// Index of the last node in `SkeletonGraph::nodes`
+    pub end: usize,
+    /// Vertices of the polyline. The first and the last vertices are always
+    /// the node pixels.
+    pub points: Vec<(usize, usize)>,
+}
+
+fn distance_to_segment(p: (usize, usize), a: (usize, usize), b: (usize, usize)) -> f64 {
+    let (px, py) = (p.0 as f64, p.1 as f64);
+    let (ax, ay) = (a.0 as f64, a.1 as f64);
+    let (bx, by) = (b.0 as f64, b.1 as f64);
+
+    let (dx, dy) = (bx - ax, by - ay);
+    let len_sq = dx * dx + dy * dy;
+    if len_sq == 0.0 {
+        return ((px - ax).powi(2) + (py - ay).powi(2)).sqrt();
+    }
+
+    let t = (((px - ax) * dx + (py - ay) * dy) / len_sq).clamp(0.0, 1.0);
+    ((px - ax - t * dx).powi(2) + (py - ay - t * dy).powi(2)).sqrt()
+}
+
+fn triangle_area(a: (usize, usize), b: (usize, usize), c: (usize, usize)) -> f64 {
+    let (ax, ay) = (a.0 as f64, a.1 as f64);
+    let (bx, by) = (b.0 as f64, b.1 as f64);
+    let (cx, cy) = (c.0 as f64, c.1 as f64);
+
+    ((bx - ax) * (cy - ay) - (cx - ax) * (by - ay)).abs() / 2.0
+}
+
+fn douglas_peucker_keep(points: &[(usize, usize)], tolerance: f64, keep: &mut [bool]) {
+    if points.len() < 3 {
+        return;
+    }
+
+    let first = points[0];
+    let last = points[points.len() - 1];
+
+    let (index, distance) = points
+        .iter()
+        .enumerate()
+        .skip(1)
+        .take(points.len() - 2)
+        .map(|(i, p)| (i, distance_to_segment(*p, first, last)))
+        .fold((0, -1.0), |max, cur| if cur.1 > max.1 { cur } else { max });
+
+    if distance > tolerance {
+        keep[index] = true;
+        douglas_peucker_keep(&points[..=index], tolerance, &mut keep[..=index]);
+        douglas_peucker_keep(&points[index..], tolerance, &mut keep[index..]);
+    }
+}
+
+/// Simplifying a pixel path with the Douglas-Peucker algorithm. The first and
+/// the last points are always kept.
+pub fn simplify_douglas_peucker(points: &[(usize, usize)], tolerance: f64) -> Vec<(usize, usize)> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+
+    if points[0] == points[points.len() - 1] {
+        // Closed paths are split at the point farthest from the start
+        let (split, _) = points
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (i, distance_to_segment(*p, points[0], points[0])))
+            .fold((0, -1.0), |max, cur| if cur.1 > max.1 { cur } else { max });
+        keep[split] = true;
+        douglas_peucker_keep(&points[..=split], tolerance, &mut keep[..=split]);
+        douglas_peucker_keep(&points[split..], tolerance, &mut keep[split..]);
+    } else {
+        douglas_peucker_keep(points, tolerance, &mut keep);
+    }
+
+    points
+        .iter()
+        .zip(keep.iter())
+        .filter(|(_, k)| **k)
+        .map(|(p, _)| *p)
+        .collect()
+}
+
+/// Simplifying a pixel path with the Visvalingam-Whyatt algorithm. Points
+/// forming a triangle smaller than `min_area` with their neighbors are
+/// removed, smallest first. The first and the last points are always kept.
+pub fn simplify_visvalingam(points: &[(usize, usize)], min_area: f64) -> Vec<(usize, usize)> {
+    let mut kept = points.to_vec();
+    // A closed path needs at least a triangle to stay closed
+    let min_len = if points.len() > 1 && points[0] == points[points.len() - 1] {
+        4
+    } else {
+        2
+    };
+
+    while kept.len() > min_len {
+        let smallest = (1..kept.len() - 1)
+            .map(|i| (i, triangle_area(kept[i - 1], kept[i], kept[i + 1])))
+            .fold(
+                (0, f64::MAX),
+                |min, cur| if cur.1 < min.1 { cur } else { min },
+            );
+
+        if smallest.1 >= min_area {
+            break;
+        }
+        kept.remove(smallest.0);
+    }
+
+    kept
+}
+
+/// Converting every edge of a traced skeleton into a polyline
+pub fn vectorise(graph: &SkeletonGraph, simplification: Simplification) -> Vec<Polyline> {
+    graph
+        .edges
+        .iter()
+        .map(|edge| Polyline {
+            start: edge.start,
+            end: edge.end,
+            points: match simplification {
+                Simplification::None => edge.pixels.clone(),
+                Simplification::DouglasPeucker(tolerance) => {
+                    simplify_douglas_peucker(&edge.pixels, tolerance)
+                }
+                Simplification::Visvalingam(min_area) => {
+                    simplify_visvalingam(&edge.pixels, min_area)
+                }
+            },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_douglas_peucker() {
+        let points = vec![(0, 0), (1, 0), (2, 1), (3, 1), (4, 2), (5, 5), (6, 8)];
+
+        assert_eq!(
+            simplify_douglas_peucker(&points, 1.0),
+            vec![(0, 0), (4, 2), (6, 8)]
+        );
+        // (5, 5) lies on the segment from (4, 2) to (6, 8)
+        assert_eq!(
+            simplify_douglas_peucker(&points, 0.0),
+            vec![(0, 0), (1, 0), (2, 1), (3, 1), (4, 2), (6, 8)]
+        );
+        assert_eq!(
+            simplify_douglas_peucker(&points, 100.0),
+            vec![(0, 0), (6, 8)]
+        );
+    }
+
+    #[test]
+    fn test_douglas_peucker_closed() {
+        let points = vec![(1, 0), (2, 0), (3, 1), (2, 2), (1, 2), (0, 1), (1, 0)];
+
+        let simplified = simplify_douglas_peucker(&points, 100.0);
+        assert_eq!(simplified.first(), simplified.last());
+        assert_eq!(simplified.len(), 3);
+    }
+
+    #[test]
+    fn test_visvalingam() {
+        let points = vec![(0, 0), (1, 0), (2, 0), (3, 0), (3, 1), (3, 2)];
+
+        assert_eq!(
+            simplify_visvalingam(&points, 0.5),
+            vec![(0, 0), (3, 0), (3, 2)]
+        );
+        assert_eq!(simplify_visvalingam(&points, 100.0), vec![(0, 0), (3, 2)]);
+    }
+
+    #[test]
+    fn test_vectorise_keeps_nodes() {
+        let img = BinImage::try_from(vec![
+            vec![true, false, false, false, false, false, false],
+            vec![false, true, true, false, false, false, false],
+            vec![false, false, false, true, true, true, true],
+            vec![false, false, false, true, false, false, false],
+            vec![false, false, false, true, false, false, false],
+        ])
+        .unwrap();
+
+        let graph = trace_skeleton(&img);
+        let polylines = vectorise(&graph, Simplification::DouglasPeucker(2.0));
+
+        assert_eq!(polylines.len(), graph.edges.len());
+        for polyline in polylines.iter() {
+            let start = &graph.nodes[polyline.start];
+            let end = &graph.nodes[polyline.end];
+            assert_eq!(polyline.points.first(), Some(&(start.x, start.y)));
+            assert_eq!(polyline.points.last(), Some(&(end.x, end.y)));
+        }
+    }
+}
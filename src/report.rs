@@ -0,0 +1,123 @@
+// Statistics of a thinning run
+#[cfg(test)]
+use crate::bin_image::BinImage;
+use crate::common::thin;
+use crate::options::ThinOptions;
+#[cfg(test)]
+use std::convert::TryFrom;
+use std::io::Error;
+#[cfg(test)]
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Wall-clock time of the phases of a thinning run
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ThinTimings {
+    /// Checking the input and the options
+    pub setup: Duration,
+    /// All sub-iterations
+    pub thinning: Duration,
+    /// Removing redundant pixels with `ThinOptions::unit_thickness`
+    pub unit_thickness: Duration,
+}
+
+impl ThinTimings {
+    pub fn total(&self) -> Duration {
+        self.setup + self.thinning + self.unit_thickness
+    }
+}
+
+/// Report of `imgthin_with_report`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ThinReport {
+    /// Number of iterations of two sub-iterations. An iteration stopped
+    /// after its first sub-iteration is counted.
+    pub iterations: usize,
+    pub sub_iterations: usize,
+    /// Number of pixels deleted in every sub-iteration
+    pub deleted: Vec<usize>,
+    /// Number of pixels removed by `ThinOptions::unit_thickness`
+    pub redundant_removed: usize,
+    pub initial_foreground: usize,
+    pub final_foreground: usize,
+    /// `false` when thinning was stopped by `ThinOptions::max_iterations`
+    pub converged: bool,
+    pub timings: ThinTimings,
+}
+
+impl ThinReport {
+    /// Number of pixels deleted by all sub-iterations
+    pub fn total_deleted(&self) -> usize {
+        self.deleted.iter().sum()
+    }
+}
+
+/// Thinning like `imgthin_with_options`, and reporting the number of
+/// iterations, the deleted pixels and the time taken. The time is measured
+/// with `std::time::Instant`, which is not available on every target.
+pub fn imgthin_with_report(
+    pixels: Vec<Vec<bool>>,
+    options: &ThinOptions,
+) -> Result<(Vec<Vec<bool>>, ThinReport), Error> {
+    let mut report = ThinReport::default();
+    let (thinned, _) = thin(
+        pixels,
+        options,
+        &crate::CONDITIONS,
+        Some(&mut report),
+        |_, _, _| {},
+    )?;
+
+    Ok((thinned, report))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let img = BinImage::try_from(PathBuf::from("./test_data/b_char.txt")).unwrap();
+
+        let (thinned, report) =
+            imgthin_with_report(img.get_pixels().to_vec(), &crate::imgthin_options()).unwrap();
+
+        assert_eq!(thinned, crate::imgthin(img.get_pixels().to_vec()).unwrap());
+        assert!(report.converged);
+        assert_eq!(report.sub_iterations, report.deleted.len());
+        assert_eq!(report.iterations, report.sub_iterations.div_ceil(2));
+        assert_eq!(report.deleted.last(), Some(&0));
+        assert_eq!(report.initial_foreground, img.foreground().count());
+        assert_eq!(
+            report.final_foreground,
+            BinImage::try_from(thinned).unwrap().foreground().count()
+        );
+        assert_eq!(
+            report.total_deleted(),
+            report.initial_foreground - report.final_foreground
+        );
+        assert_eq!(report.redundant_removed, 0);
+
+        // Stopping after the first iteration
+        let options = ThinOptions {
+            max_iterations: Some(1),
+            ..crate::imgthin_options()
+        };
+        let (_, stopped) = imgthin_with_report(img.get_pixels().to_vec(), &options).unwrap();
+        assert!(!stopped.converged);
+        assert_eq!(stopped.iterations, 1);
+        assert_eq!(stopped.deleted, report.deleted[0..2]);
+
+        // Pixels removed for unit thickness are counted apart
+        let options = ThinOptions {
+            unit_thickness: true,
+            ..crate::imgthin_options()
+        };
+        let (thinned, unit) = imgthin_with_report(img.get_pixels().to_vec(), &options).unwrap();
+        assert_eq!(unit.deleted, report.deleted);
+        assert_eq!(
+            unit.redundant_removed,
+            report.final_foreground - BinImage::try_from(thinned).unwrap().foreground().count()
+        );
+    }
+}
@@ -0,0 +1,259 @@
+// Tracing thinned images into a graph of nodes and pixel paths
+use crate::bin_image::BinImage;
+use crate::common::NEIGHBOR_OFFSETS;
+#[cfg(test)]
+use crate::thickness::remove_redundant_pixels;
+use std::collections::HashSet;
+#[cfg(test)]
+use std::convert::TryFrom;
+#[cfg(test)]
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeKind {
+    /// A pixel with exactly one neighbor
+    End,
+    /// A pixel with three or more neighbors
+    Junction,
+    /// A pixel without neighbors
+    Isolated,
+    /// An arbitrary pixel picked to open a closed loop without other nodes
+    Loop,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SkeletonNode {
+    pub x: usize,
+    pub y: usize,
+    pub kind: NodeKind,
+    /// Number of 8-connected foreground neighbors
+    pub degree: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SkeletonEdge {
+    /// Index of the first node in `SkeletonGraph::nodes`
+    pub start: usize,
+    /// Index of the last node in `SkeletonGraph::nodes`
+    pub end: usize,
+    /// Pixels from the start node to the end node, both included
+    pub pixels: Vec<(usize, usize)>,
+}
+
+impl SkeletonEdge {
+    /// Length of the pixel path, counting diagonal steps as `sqrt(2)`
+    pub fn length(&self) -> f64 {
+        self.pixels
+            .windows(2)
+            .map(|w| {
+                if w[0].0 != w[1].0 && w[0].1 != w[1].1 {
+                    std::f64::consts::SQRT_2
+                } else {
+                    1.0
+                }
+            })
+            .sum()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.start == self.end && self.pixels.len() > 1
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SkeletonGraph {
+    pub nodes: Vec<SkeletonNode>,
+    pub edges: Vec<SkeletonEdge>,
+}
+
+impl SkeletonGraph {
+    /// Indexes of the edges which start or end at the given node
+    pub fn node_edges(&self, node: usize) -> Vec<usize> {
+        self.edges
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.start == node || e.end == node)
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+// Foreground neighbors in the order of P2, P3, .. P9
+pub(crate) fn foreground_neighbors(image: &BinImage, x: usize, y: usize) -> Vec<(usize, usize)> {
+    image
+        .get_neighbors(x, y)
+        .to_array()
+        .iter()
+        .zip(NEIGHBOR_OFFSETS.iter())
+        .filter(|(p, _)| **p)
+        .map(|(_, (dx, dy))| ((x as isize + dx) as usize, (y as isize + dy) as usize))
+        .collect()
+}
+
+/// Tracing a thinned image into nodes and the pixel paths between them. The
+/// image should be one pixel thick, otherwise the extra pixels show up as
+/// junctions. See `remove_redundant_pixels`.
+pub fn trace_skeleton(image: &BinImage) -> SkeletonGraph {
+    let width = image.get_width();
+    let height = image.get_height();
+
+    let mut graph = SkeletonGraph::default();
+    let mut node_index: Vec<Vec<Option<usize>>> = vec![vec![None; width]; height];
+    let mut visited = vec![vec![false; width]; height];
+
+    for (x, y) in image.foreground() {
+        let degree = foreground_neighbors(image, x, y).len();
+        let kind = match degree {
+            0 => NodeKind::Isolated,
+            1 => NodeKind::End,
+            2 => continue,
+            _ => NodeKind::Junction,
+        };
+
+        node_index[y][x] = Some(graph.nodes.len());
+        visited[y][x] = true;
+        graph.nodes.push(SkeletonNode { x, y, kind, degree });
+    }
+
+    // Directed first steps of already traced edges
+    let mut traced: HashSet<((usize, usize), (usize, usize))> = HashSet::new();
+
+    for start in 0..graph.nodes.len() {
+        let from = (graph.nodes[start].x, graph.nodes[start].y);
+
+        for first in foreground_neighbors(image, from.0, from.1) {
+            if traced.contains(&(from, first)) {
+                continue;
+            }
+
+            let mut pixels = vec![from, first];
+            let mut prev = from;
+            let mut cur = first;
+
+            while node_index[cur.1][cur.0].is_none() {
+                visited[cur.1][cur.0] = true;
+                let next = foreground_neighbors(image, cur.0, cur.1)
+                    .into_iter()
+                    .find(|p| *p != prev)
+                    .unwrap();
+                prev = cur;
+                cur = next;
+                pixels.push(cur);
+            }
+
+            traced.insert((from, first));
+            traced.insert((cur, prev));
+
+            graph.edges.push(SkeletonEdge {
+                start,
+                end: node_index[cur.1][cur.0].unwrap(),
+                pixels,
+            });
+        }
+    }
+
+    // Remaining pixels are closed loops without any node
+    for (x, y) in image.foreground() {
+        if visited[y][x] {
+            continue;
+        }
+
+        let start = graph.nodes.len();
+        graph.nodes.push(SkeletonNode {
+            x,
+            y,
+            kind: NodeKind::Loop,
+            degree: 2,
+        });
+        visited[y][x] = true;
+
+        let mut pixels = vec![(x, y)];
+        let mut prev = (x, y);
+        let mut cur = foreground_neighbors(image, x, y)[0];
+
+        while cur != (x, y) {
+            visited[cur.1][cur.0] = true;
+            pixels.push(cur);
+            let next = foreground_neighbors(image, cur.0, cur.1)
+                .into_iter()
+                .find(|p| *p != prev)
+                .unwrap();
+            prev = cur;
+            cur = next;
+        }
+        pixels.push(cur);
+
+        graph.edges.push(SkeletonEdge {
+            start,
+            end: start,
+            pixels,
+        });
+    }
+
+    graph
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_trace_line_and_junction() {
+        let img = BinImage::try_from(vec![
+            vec![true, false, false, false, true],
+            vec![false, true, false, true, false],
+            vec![false, false, true, false, false],
+            vec![false, false, true, false, false],
+            vec![false, false, true, false, false],
+        ])
+        .unwrap();
+
+        let graph = trace_skeleton(&img);
+
+        let kinds: Vec<NodeKind> = graph.nodes.iter().map(|n| n.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                NodeKind::End,
+                NodeKind::End,
+                NodeKind::Junction,
+                NodeKind::End
+            ]
+        );
+        assert_eq!(graph.edges.len(), 3);
+        assert_eq!(graph.node_edges(2).len(), 3);
+        assert_eq!(graph.edges[0].pixels, vec![(0, 0), (1, 1), (2, 2)]);
+        assert!((graph.edges[0].length() - 2.0 * std::f64::consts::SQRT_2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_trace_loop() {
+        let img = BinImage::try_from(vec![
+            vec![false, true, true, false],
+            vec![true, false, false, true],
+            vec![false, true, true, false],
+        ])
+        .unwrap();
+
+        let graph = trace_skeleton(&img);
+
+        assert_eq!(graph.nodes.len(), 1);
+        assert_eq!(graph.nodes[0].kind, NodeKind::Loop);
+        assert_eq!(graph.edges.len(), 1);
+        assert!(graph.edges[0].is_closed());
+        assert_eq!(graph.edges[0].pixels.len(), 7);
+    }
+
+    #[test]
+    fn test_trace_char_b() {
+        let mut img = BinImage::try_from(PathBuf::from("./test_data/b_char_thinned.txt")).unwrap();
+        remove_redundant_pixels(&mut img);
+
+        let graph = trace_skeleton(&img);
+
+        assert_eq!(graph.nodes.len(), 2);
+        assert!(graph.nodes.iter().all(|n| n.kind == NodeKind::Junction));
+        assert_eq!(graph.edges.len(), 3);
+        assert!(graph.edges.iter().all(|e| e.start != e.end));
+    }
+}
@@ -0,0 +1,261 @@
+// Measuring the stroke width along skeletons
+use crate::bin_image::BinImage;
+use crate::distance::distance_transform;
+use crate::skeleton::{trace_skeleton, SkeletonEdge, SkeletonGraph};
+use crate::topology::label_components;
+#[cfg(test)]
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind};
+#[cfg(test)]
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WidthStats {
+    pub mean: f64,
+    pub min: f64,
+    pub max: f64,
+    pub variance: f64,
+    /// Number of skeleton pixels measured
+    pub samples: usize,
+}
+
+impl WidthStats {
+    /// Statistics of the given widths. Returns `None` for an empty slice.
+    pub fn from_widths(widths: &[f64]) -> Option<WidthStats> {
+        if widths.is_empty() {
+            return None;
+        }
+
+        let samples = widths.len();
+        let mean = widths.iter().sum::<f64>() / samples as f64;
+
+        Some(WidthStats {
+            mean,
+            min: widths.iter().cloned().fold(f64::MAX, f64::min),
+            max: widths.iter().cloned().fold(f64::MIN, f64::max),
+            variance: widths.iter().map(|w| (w - mean).powi(2)).sum::<f64>() / samples as f64,
+            samples,
+        })
+    }
+}
+
+/// Stroke width of a glyph. Glyphs are the 8-connected components of the
+/// skeleton, identified by their first pixel in raster order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GlyphWidth {
+    pub component: (usize, usize),
+    pub stats: WidthStats,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StrokeWidthReport {
+    /// Local stroke width at every skeleton pixel, 0 for other pixels
+    pub local: Vec<Vec<f64>>,
+    pub graph: SkeletonGraph,
+    /// Width statistics of every edge, in the order of `graph.edges`
+    pub edges: Vec<WidthStats>,
+    pub glyphs: Vec<GlyphWidth>,
+}
+
+/// Stroke width at a skeleton pixel from the distance map of the original
+/// image. A pixel at a distance of `d` from the background lies in a stroke
+/// `2d - 1` pixels wide.
+pub fn local_stroke_width(distance: &[Vec<f64>], x: usize, y: usize) -> f64 {
+    match distance.get(y).and_then(|row| row.get(x)) {
+        Some(d) if *d > 0.0 => 2.0 * d - 1.0,
+        _ => 0.0,
+    }
+}
+
+impl SkeletonEdge {
+    /// Width statistics along the edge. `distance` is the distance map of the
+    /// original image, see `distance_transform`. Pixels on the background of
+    /// the original image have no width and are skipped. Returns `None` when
+    /// no pixel is left.
+    pub fn stroke_width(&self, distance: &[Vec<f64>]) -> Option<WidthStats> {
+        let widths: Vec<f64> = self
+            .pixels
+            .iter()
+            .map(|(x, y)| local_stroke_width(distance, *x, *y))
+            .filter(|w| *w > 0.0)
+            .collect();
+
+        WidthStats::from_widths(&widths)
+    }
+}
+
+impl SkeletonGraph {
+    /// Width statistics of every edge, in the order of `edges`, see
+    /// `SkeletonEdge::stroke_width`
+    pub fn stroke_widths(&self, distance: &[Vec<f64>]) -> Vec<Option<WidthStats>> {
+        self.edges
+            .iter()
+            .map(|e| e.stroke_width(distance))
+            .collect()
+    }
+}
+
+/// Measuring the stroke width of the original image along its skeleton. Fails
+/// when a pixel of the skeleton is on the background of the original image,
+/// as it has no width.
+pub fn measure_stroke_width(
+    original: &BinImage,
+    skeleton: &BinImage,
+) -> Result<StrokeWidthReport, Error> {
+    if original.get_width() != skeleton.get_width()
+        || original.get_height() != skeleton.get_height()
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Images have different dimensions",
+        ));
+    }
+
+    if let Some((x, y)) = skeleton
+        .foreground()
+        .find(|(x, y)| !original.get_value(*x, *y).unwrap())
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Skeleton pixel ({}, {}) is not in the original image", x, y),
+        ));
+    }
+
+    let distance = distance_transform(original);
+    let graph = trace_skeleton(skeleton);
+    // Every pixel of the skeleton has a width, so every edge has statistics
+    let edges = graph
+        .stroke_widths(&distance)
+        .into_iter()
+        .collect::<Option<Vec<WidthStats>>>()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Skeleton edge without pixels"))?;
+
+    let mut local = vec![vec![0.0; skeleton.get_width()]; skeleton.get_height()];
+    let mut glyphs = vec![];
+
+    if skeleton.get_width() > 0 {
+        let labels = label_components(skeleton, true, true);
+        let mut widths: Vec<Vec<f64>> = vec![vec![]; labels.seeds.len()];
+
+        for (y, row) in labels.labels.iter().enumerate() {
+            for (x, label) in row.iter().enumerate() {
+                if *label == 0 {
+                    continue;
+                }
+                let width = local_stroke_width(&distance, x, y);
+                local[y][x] = width;
+                widths[label - 1].push(width);
+            }
+        }
+
+        glyphs = labels
+            .seeds
+            .iter()
+            .zip(widths.iter())
+            .map(|(seed, widths)| GlyphWidth {
+                component: *seed,
+                stats: WidthStats::from_widths(widths).unwrap(),
+            })
+            .collect();
+    }
+
+    Ok(StrokeWidthReport {
+        local,
+        graph,
+        edges,
+        glyphs,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_width_stats() {
+        let stats = WidthStats::from_widths(&[1.0, 3.0, 5.0]).unwrap();
+
+        assert_eq!(stats.mean, 3.0);
+        assert_eq!(stats.min, 1.0);
+        assert_eq!(stats.max, 5.0);
+        assert!((stats.variance - 8.0 / 3.0).abs() < 1e-9);
+        assert_eq!(stats.samples, 3);
+        assert!(WidthStats::from_widths(&[]).is_none());
+    }
+
+    #[test]
+    fn test_measure_stroke_width() {
+        let mut original = BinImage::new(12, 9, false);
+        let mut skeleton = BinImage::new(12, 9, false);
+        // A horizontal bar 5 pixels thick and a separate 1 pixel line
+        for x in 1..9 {
+            for y in 1..6 {
+                original.set_value(x, y, true).unwrap();
+            }
+            skeleton.set_value(x, 3, true).unwrap();
+        }
+        for y in 1..8 {
+            original.set_value(11, y, true).unwrap();
+            skeleton.set_value(11, y, true).unwrap();
+        }
+
+        let report = measure_stroke_width(&original, &skeleton).unwrap();
+
+        assert_eq!(report.glyphs.len(), 2);
+        assert_eq!(report.glyphs[0].component, (11, 1));
+        assert_eq!(report.glyphs[0].stats.mean, 1.0);
+        assert_eq!(report.glyphs[1].component, (1, 3));
+        assert_eq!(report.glyphs[1].stats.max, 5.0);
+        assert_eq!(report.glyphs[1].stats.min, 1.0);
+        assert_eq!(report.local[3][4], 5.0);
+        assert_eq!(report.local[0][0], 0.0);
+
+        assert_eq!(report.edges.len(), report.graph.edges.len());
+        assert_eq!(report.edges[0].samples, 7);
+        assert_eq!(report.edges[1].samples, 8);
+    }
+
+    #[test]
+    fn test_measure_char_b() {
+        let original = BinImage::try_from(PathBuf::from("./test_data/b_char.txt")).unwrap();
+        let skeleton = BinImage::try_from(PathBuf::from("./test_data/b_char_thinned.txt")).unwrap();
+
+        let report = measure_stroke_width(&original, &skeleton).unwrap();
+
+        assert_eq!(report.glyphs.len(), 1);
+        assert!(report.glyphs[0].stats.mean > 2.0 && report.glyphs[0].stats.mean < 6.0);
+        assert!(measure_stroke_width(&original, &BinImage::new(1, 1, false)).is_err());
+
+        // A skeleton pixel on the background has no width
+        let mut outside = skeleton.clone();
+        outside.set_value(0, 0, true).unwrap();
+        assert!(!original.get_value(0, 0).unwrap());
+        assert!(measure_stroke_width(&original, &outside).is_err());
+    }
+
+    #[test]
+    fn test_edge_stroke_width() {
+        let mut original = BinImage::new(5, 3, false);
+        for x in 0..4 {
+            original.set_value(x, 1, true).unwrap();
+        }
+        let distance = distance_transform(&original);
+
+        // The pixel on the background is skipped
+        let edge = SkeletonEdge {
+            start: 0,
+            end: 1,
+            pixels: vec![(2, 1), (3, 1), (4, 1)],
+        };
+        let stats = edge.stroke_width(&distance).unwrap();
+        assert_eq!(stats.samples, 2);
+        assert_eq!(stats.min, 1.0);
+
+        let empty = SkeletonEdge {
+            start: 0,
+            end: 0,
+            pixels: vec![],
+        };
+        assert!(empty.stroke_width(&distance).is_none());
+    }
+}
@@ -0,0 +1,238 @@
+// Rendering images, skeletons and traced paths as SVG
+use crate::bin_image::BinImage;
+use crate::polyline::Polyline;
+#[cfg(test)]
+use crate::polyline::{vectorise, Simplification};
+#[cfg(test)]
+use crate::skeleton::trace_skeleton;
+use crate::skeleton::{NodeKind, SkeletonGraph};
+#[cfg(test)]
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind, Write};
+
+/// Layers to draw in the SVG document, from the bottom to the top. Every
+/// layer is optional.
+#[derive(Clone, Debug, Default)]
+pub struct SvgLayers<'a> {
+    /// The image before thinning, drawn in light gray
+    pub original: Option<&'a BinImage>,
+    /// The thinned image, drawn in black
+    pub skeleton: Option<&'a BinImage>,
+    /// Traced edges are drawn as paths when `polylines` is not given, and
+    /// nodes are marked with circles
+    pub graph: Option<&'a SkeletonGraph>,
+    /// Vectorised paths, drawn in red
+    pub polylines: Option<&'a [Polyline]>,
+}
+
+fn dimensions(layers: &SvgLayers) -> Result<(usize, usize), Error> {
+    let mut size: Option<(usize, usize)> = None;
+
+    for image in [layers.original, layers.skeleton].iter().flatten() {
+        let image_size = (image.get_width(), image.get_height());
+        match size {
+            Some(s) if s != image_size => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "Images have different dimensions",
+                ))
+            }
+            _ => size = Some(image_size),
+        }
+    }
+
+    if let Some(size) = size {
+        return Ok(size);
+    }
+
+    // Without images the size is taken from the paths
+    let mut points: Vec<(usize, usize)> = vec![];
+    if let Some(graph) = layers.graph {
+        points.extend(graph.nodes.iter().map(|n| (n.x, n.y)));
+        points.extend(graph.edges.iter().flat_map(|e| e.pixels.iter().cloned()));
+    }
+    if let Some(polylines) = layers.polylines {
+        points.extend(polylines.iter().flat_map(|p| p.points.iter().cloned()));
+    }
+
+    Ok((
+        points.iter().map(|p| p.0 + 1).max().unwrap_or(0),
+        points.iter().map(|p| p.1 + 1).max().unwrap_or(0),
+    ))
+}
+
+fn write_pixels<W: Write>(
+    writer: &mut W,
+    image: &BinImage,
+    id: &str,
+    fill: &str,
+) -> Result<(), Error> {
+    writeln!(
+        writer,
+        "<g id=\"{}\" inkscape:groupmode=\"layer\" inkscape:label=\"{}\" fill=\"{}\">",
+        id, id, fill
+    )?;
+
+    // Horizontal runs of pixels are merged into a single rectangle
+    for (y, row) in image.get_pixels().iter().enumerate() {
+        let mut x = 0;
+        while x < row.len() {
+            if !row[x] {
+                x += 1;
+                continue;
+            }
+            let start = x;
+            while x < row.len() && row[x] {
+                x += 1;
+            }
+            writeln!(
+                writer,
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"1\"/>",
+                start,
+                y,
+                x - start
+            )?;
+        }
+    }
+
+    writeln!(writer, "</g>")
+}
+
+fn write_path<W: Write>(writer: &mut W, points: &[(usize, usize)]) -> Result<(), Error> {
+    let coords: Vec<String> = points
+        .iter()
+        .map(|(x, y)| format!("{}.5,{}.5", x, y))
+        .collect();
+
+    writeln!(writer, "<polyline points=\"{}\"/>", coords.join(" "))
+}
+
+/// Writing the given layers as an SVG document. One pixel is one user unit.
+pub fn write_svg<W: Write>(writer: &mut W, layers: &SvgLayers) -> Result<(), Error> {
+    let (width, height) = dimensions(layers)?;
+
+    writeln!(
+        writer,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" \
+         xmlns:inkscape=\"http://www.inkscape.org/namespaces/inkscape\" \
+         width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" shape-rendering=\"crispEdges\">",
+        width, height, width, height
+    )?;
+
+    if let Some(original) = layers.original {
+        write_pixels(writer, original, "original", "#d0d0d0")?;
+    }
+
+    if let Some(skeleton) = layers.skeleton {
+        write_pixels(writer, skeleton, "skeleton", "#000000")?;
+    }
+
+    let paths: Option<Vec<&[(usize, usize)]>> = match (layers.polylines, layers.graph) {
+        (Some(polylines), _) => Some(polylines.iter().map(|p| p.points.as_slice()).collect()),
+        (None, Some(graph)) => Some(graph.edges.iter().map(|e| e.pixels.as_slice()).collect()),
+        (None, None) => None,
+    };
+
+    if let Some(paths) = paths {
+        writeln!(
+            writer,
+            "<g id=\"paths\" inkscape:groupmode=\"layer\" inkscape:label=\"paths\" \
+             fill=\"none\" stroke=\"#e00000\" stroke-width=\"0.3\" \
+             stroke-linejoin=\"round\" shape-rendering=\"geometricPrecision\">"
+        )?;
+        for points in paths {
+            write_path(writer, points)?;
+        }
+        writeln!(writer, "</g>")?;
+    }
+
+    if let Some(graph) = layers.graph {
+        writeln!(
+            writer,
+            "<g id=\"nodes\" inkscape:groupmode=\"layer\" inkscape:label=\"nodes\" \
+             shape-rendering=\"geometricPrecision\">"
+        )?;
+        for node in graph.nodes.iter() {
+            let fill = match node.kind {
+                NodeKind::End => "#00a000",
+                NodeKind::Junction => "#0000e0",
+                NodeKind::Isolated => "#a000a0",
+                NodeKind::Loop => "#e0a000",
+            };
+            writeln!(
+                writer,
+                "<circle cx=\"{}.5\" cy=\"{}.5\" r=\"0.6\" fill=\"{}\"/>",
+                node.x, node.y, fill
+            )?;
+        }
+        writeln!(writer, "</g>")?;
+    }
+
+    writeln!(writer, "</svg>")
+}
+
+/// Rendering the given layers as an SVG string
+pub fn to_svg(layers: &SvgLayers) -> Result<String, Error> {
+    let mut buffer = vec![];
+    write_svg(&mut buffer, layers)?;
+
+    String::from_utf8(buffer).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_to_svg() {
+        let original = BinImage::try_from(vec![
+            vec![true, true, true, false],
+            vec![true, true, true, false],
+            vec![true, true, true, true],
+        ])
+        .unwrap();
+        let skeleton = BinImage::try_from(vec![
+            vec![false, true, false, false],
+            vec![false, true, false, false],
+            vec![false, false, true, true],
+        ])
+        .unwrap();
+        let graph = trace_skeleton(&skeleton);
+        let polylines = vectorise(&graph, Simplification::DouglasPeucker(0.5));
+
+        let svg = to_svg(&SvgLayers {
+            original: Some(&original),
+            skeleton: Some(&skeleton),
+            graph: Some(&graph),
+            polylines: Some(&polylines),
+        })
+        .unwrap();
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains("viewBox=\"0 0 4 3\""));
+        assert!(svg.contains("<rect x=\"0\" y=\"2\" width=\"4\" height=\"1\"/>"));
+        assert!(svg.contains("<rect x=\"2\" y=\"2\" width=\"2\" height=\"1\"/>"));
+        assert!(svg.contains("<polyline points=\"1.5,0.5 1.5,1.5 3.5,2.5\"/>"));
+        assert_eq!(svg.matches("<circle ").count(), 2);
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn test_to_svg_dimensions() {
+        let graph = trace_skeleton(&BinImage::try_from(vec![vec![true, true, false]]).unwrap());
+
+        let svg = to_svg(&SvgLayers {
+            graph: Some(&graph),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(svg.contains("viewBox=\"0 0 2 1\""));
+
+        let result = to_svg(&SvgLayers {
+            original: Some(&BinImage::new(3, 3, false)),
+            skeleton: Some(&BinImage::new(2, 3, false)),
+            ..Default::default()
+        });
+        assert!(result.is_err());
+    }
+}
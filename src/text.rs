@@ -0,0 +1,424 @@
+// Reading and writing binary images as text
+use crate::bin_image::BinImage;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{Error, ErrorKind, Read, Write};
+
+/// Error of a text image at a position in the text. It is the inner error of
+/// the `std::io::Error` returned by `TextFormat::parse` and `TextFormat::read`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextParseError {
+    /// Line number, counted from 1
+    pub line: usize,
+    /// Character position in the line, counted from 1
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TextParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for TextParseError {}
+
+fn parse_error(line: usize, column: usize, message: String) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        TextParseError {
+            line,
+            column,
+            message,
+        },
+    )
+}
+
+/// Text format of a binary image, one line for every row.
+///
+/// Whitespace between pixels and blank lines are ignored when reading. With
+/// `rulers`, pixels are separated by `|` and every row starts with its
+/// number, below lines with the column numbers:
+///
+/// ```text
+///  |0|1|2|
+/// 0|_|X|_|
+/// 1|X|X|X|
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextFormat {
+    pub foreground: char,
+    pub background: char,
+    /// Lines starting with this character are comments, which are skipped
+    /// when reading. It must differ from the pixel characters, so a format
+    /// with `#` as a pixel character needs another comment character or
+    /// `None`.
+    pub comment: Option<char>,
+    /// Coordinate rulers around the pixels
+    pub rulers: bool,
+}
+
+impl Default for TextFormat {
+    /// `1` and `0` without rulers, the format of the test fixtures
+    fn default() -> TextFormat {
+        TextFormat {
+            foreground: '1',
+            background: '0',
+            comment: Some('#'),
+            rulers: false,
+        }
+    }
+}
+
+// Number of decimal digits of a number
+fn digits(number: usize) -> usize {
+    number.to_string().len()
+}
+
+// Writing a grid of symbols with coordinate rulers, see `TextFormat`
+pub(crate) fn write_grid<W: Write, F: Fn(usize, usize) -> char>(
+    writer: &mut W,
+    width: usize,
+    height: usize,
+    symbol: F,
+) -> Result<(), Error> {
+    let x_digits = digits(width.saturating_sub(1));
+    let y_digits = digits(height.saturating_sub(1));
+
+    if width > 0 {
+        // One line for every digit of the column numbers
+        let columns: Vec<Vec<char>> = (0..width)
+            .map(|x| format!("{:>1$}", x, x_digits).chars().collect())
+            .collect();
+
+        for digit in 0..x_digits {
+            let mut line = " ".repeat(y_digits);
+            line.push('|');
+            for column in columns.iter() {
+                line.push(column[digit]);
+                line.push('|');
+            }
+            writeln!(writer, "{}", line)?;
+        }
+    }
+
+    for y in 0..height {
+        let mut line = format!("{:>1$}|", y, y_digits);
+        for x in 0..width {
+            line.push(symbol(x, y));
+            line.push('|');
+        }
+        writeln!(writer, "{}", line)?;
+    }
+
+    Ok(())
+}
+
+impl TextFormat {
+    /// `X` and `_` with rulers, the format of `Display` for `BinImage`
+    pub fn grid() -> TextFormat {
+        TextFormat {
+            foreground: 'X',
+            background: '_',
+            comment: Some('#'),
+            rulers: true,
+        }
+    }
+
+    fn check(&self) -> Result<(), Error> {
+        if self.foreground == self.background {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Foreground and background characters are the same",
+            ));
+        }
+        if self.comment == Some(self.foreground) || self.comment == Some(self.background) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Comment character is also a pixel character, set `comment` to another character or `None`",
+            ));
+        }
+        Ok(())
+    }
+
+    // Pixels of a line, or `None` for lines without pixels. `row` is the
+    // number of rows read so far, which the row ruler must match.
+    fn parse_line(
+        &self,
+        number: usize,
+        row: usize,
+        line: &str,
+    ) -> Result<Option<Vec<bool>>, Error> {
+        let chars: Vec<char> = line.chars().collect();
+        let is_blank =
+            |c: &char| c.is_whitespace() && *c != self.foreground && *c != self.background;
+
+        let first = chars.iter().position(|c| !is_blank(c));
+        let first = match first {
+            Some(first) => first,
+            None => return Ok(None),
+        };
+        if Some(chars[first]) == self.comment {
+            return Ok(None);
+        }
+
+        let mut pixels = vec![];
+        let mut start = 0;
+
+        if self.rulers {
+            // The row number before the first bar. Only the column rulers
+            // have no row number.
+            let bar = match chars.iter().position(|c| *c == '|') {
+                Some(bar) => bar,
+                None => {
+                    return Err(parse_error(
+                        number,
+                        chars.len() + 1,
+                        String::from("Missing ruler"),
+                    ))
+                }
+            };
+            if let Some(i) = (0..bar).find(|i| !chars[*i].is_ascii_digit() && chars[*i] != ' ') {
+                return Err(parse_error(
+                    number,
+                    i + 1,
+                    format!("Unexpected character '{}' in the ruler", chars[i]),
+                ));
+            }
+            if chars[..bar].iter().all(|c| *c == ' ') {
+                return Ok(None);
+            }
+
+            let ruler: String = chars[..bar].iter().collect();
+            if ruler.trim().parse::<usize>().ok() != Some(row) {
+                let column = chars.iter().position(|c| *c != ' ').unwrap();
+                return Err(parse_error(
+                    number,
+                    column + 1,
+                    format!("Expected row {}, found '{}'", row, ruler.trim()),
+                ));
+            }
+            start = bar + 1;
+
+            // Every pixel is followed by a bar
+            if (chars.len() - start) % 2 == 1 {
+                return Err(parse_error(
+                    number,
+                    chars.len() + 1,
+                    String::from("Missing '|' after the last pixel"),
+                ));
+            }
+        }
+
+        for (i, c) in chars.iter().enumerate().skip(start) {
+            // Every other character is a bar with rulers
+            if self.rulers && (i - start) % 2 == 1 {
+                if *c != '|' {
+                    return Err(parse_error(
+                        number,
+                        i + 1,
+                        format!("Expected '|', found '{}'", c),
+                    ));
+                }
+                continue;
+            }
+
+            if *c == self.foreground {
+                pixels.push(true);
+            } else if *c == self.background {
+                pixels.push(false);
+            } else if self.rulers || !is_blank(c) {
+                return Err(parse_error(
+                    number,
+                    i + 1,
+                    format!("Unexpected character '{}'", c),
+                ));
+            }
+        }
+
+        Ok(Some(pixels))
+    }
+
+    /// Parsing an image from text
+    pub fn parse(&self, text: &str) -> Result<BinImage, Error> {
+        self.check()?;
+
+        let mut pixels: Vec<Vec<bool>> = vec![];
+
+        for (i, line) in text.lines().enumerate() {
+            let row = match self.parse_line(i + 1, pixels.len(), line)? {
+                Some(row) => row,
+                None => continue,
+            };
+
+            if let Some(first) = pixels.first() {
+                if first.len() != row.len() {
+                    return Err(parse_error(
+                        i + 1,
+                        line.chars().count() + 1,
+                        format!("Expected {} pixels, found {}", first.len(), row.len()),
+                    ));
+                }
+            }
+            pixels.push(row);
+        }
+
+        BinImage::try_from(pixels)
+    }
+
+    /// Reading an image from text
+    pub fn read<R: Read>(&self, mut reader: R) -> Result<BinImage, Error> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+
+        self.parse(&text)
+    }
+
+    /// Writing an image as text. Every line ends with a line break.
+    pub fn write<W: Write>(&self, writer: &mut W, image: &BinImage) -> Result<(), Error> {
+        self.check()?;
+
+        let symbol = |x: usize, y: usize| {
+            if image.get_value(x, y).unwrap() {
+                self.foreground
+            } else {
+                self.background
+            }
+        };
+
+        if self.rulers {
+            return write_grid(writer, image.get_width(), image.get_height(), symbol);
+        }
+
+        for y in 0..image.get_height() {
+            let line: String = (0..image.get_width()).map(|x| symbol(x, y)).collect();
+            writeln!(writer, "{}", line)?;
+        }
+
+        Ok(())
+    }
+
+    /// Rendering an image as text
+    pub fn render(&self, image: &BinImage) -> Result<String, Error> {
+        let mut buffer = vec![];
+        self.write(&mut buffer, image)?;
+
+        String::from_utf8(buffer).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn position(error: Error) -> (usize, usize) {
+        let error = error
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<TextParseError>())
+            .unwrap();
+        (error.line, error.column)
+    }
+
+    #[test]
+    fn test_parse() {
+        let format = TextFormat::default();
+
+        let img = format.parse("# A comment\n\n101\n0 1 0\r\n").unwrap();
+        assert_eq!(
+            img.get_pixels(),
+            &vec![vec![true, false, true], vec![false, true, false]]
+        );
+        assert_eq!(format.parse("").unwrap().get_height(), 0);
+
+        assert_eq!(position(format.parse("101\n10a\n").unwrap_err()), (2, 3));
+        assert_eq!(position(format.parse("101\n\n10\n").unwrap_err()), (3, 3));
+
+        let format = TextFormat {
+            foreground: '#',
+            background: '.',
+            comment: Some(';'),
+            rulers: false,
+        };
+        let img = format.parse("; Comment\n.#.\n##.\n").unwrap();
+        assert_eq!(img.foreground().count(), 3);
+        assert_eq!(format.render(&img).unwrap(), ".#.\n##.\n");
+        assert!(format.read("#.\n.#\n".as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn test_rulers() {
+        let mut img = BinImage::new(12, 3, false);
+        img.set_value(1, 0, true).unwrap();
+        img.set_value(11, 2, true).unwrap();
+
+        let text = TextFormat::grid().render(&img).unwrap();
+        assert_eq!(
+            text,
+            concat!(
+                " | | | | | | | | | | |1|1|\n",
+                " |0|1|2|3|4|5|6|7|8|9|0|1|\n",
+                "0|_|X|_|_|_|_|_|_|_|_|_|_|\n",
+                "1|_|_|_|_|_|_|_|_|_|_|_|_|\n",
+                "2|_|_|_|_|_|_|_|_|_|_|_|X|\n",
+            )
+        );
+        assert_eq!(TextFormat::grid().parse(&text).unwrap(), img);
+
+        // More rows than columns
+        let img = BinImage::new(2, 11, true);
+        let text = TextFormat::grid().render(&img).unwrap();
+        assert!(text.starts_with("  |0|1|\n 0|X|X|\n"));
+        assert!(text.ends_with("10|X|X|\n"));
+        assert_eq!(TextFormat::grid().parse(&text).unwrap(), img);
+
+        let error = TextFormat::grid().parse(" |0|1|\n0|X|X|\n1|X X|\n");
+        assert_eq!(position(error.unwrap_err()), (3, 4));
+        let error = TextFormat::grid().parse(" |0|1|\n0|X|X|\nX|X|\n");
+        assert_eq!(position(error.unwrap_err()), (3, 1));
+
+        // Row numbers out of order and a missing last bar
+        let error = TextFormat::grid().parse(" |0|1|\n0|X|X|\n2|X|X|\n");
+        assert_eq!(position(error.unwrap_err()), (3, 1));
+        let error = TextFormat::grid().parse(" |0|1|\n 0|X|X|\n 0|X|X|\n");
+        assert_eq!(position(error.unwrap_err()), (3, 2));
+        let error = TextFormat::grid().parse(" |0|1|\n0|X|X|\n1|X|X\n");
+        assert_eq!(position(error.unwrap_err()), (3, 6));
+    }
+
+    #[test]
+    fn test_write() {
+        let img = BinImage::try_from(vec![vec![true, false], vec![false, false]]).unwrap();
+        let mut buffer = vec![];
+        TextFormat::default().write(&mut buffer, &img).unwrap();
+        assert_eq!(buffer, b"10\n00\n");
+
+        let format = TextFormat {
+            background: '1',
+            ..TextFormat::default()
+        };
+        assert!(format.render(&img).is_err());
+        assert!(format.parse("11").is_err());
+
+        // The default comment character is the foreground of this format
+        let format = TextFormat {
+            foreground: '#',
+            background: '.',
+            ..TextFormat::default()
+        };
+        assert!(format.render(&img).is_err());
+        let error = format.parse("#.\n.#\n").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        assert!(error.to_string().contains("`comment`"));
+
+        let format = TextFormat {
+            comment: Some('0'),
+            ..TextFormat::default()
+        };
+        assert!(format.render(&img).is_err());
+        assert!(format.parse("10").is_err());
+    }
+}
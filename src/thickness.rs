@@ -0,0 +1,156 @@
+// Checking the unit thickness of thinned images
+use crate::bin_image::BinImage;
+#[cfg(test)]
+use std::convert::TryFrom;
+#[cfg(test)]
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThicknessReport {
+    /// Pixels which can be removed on their own without breaking the
+    /// 8-connectivity. Neighbouring pixels in this list may not be
+    /// removable together.
+    pub redundant: Vec<(usize, usize)>,
+    /// Number of foreground pixels
+    pub foreground: usize,
+    /// Number of fully filled 2x2 blocks
+    pub blocks: usize,
+}
+
+impl ThicknessReport {
+    pub fn is_unit_thick(&self) -> bool {
+        self.redundant.is_empty()
+    }
+}
+
+// A foreground pixel is redundant when it is not an end point and removing it
+// does not change the 8-connectivity of its neighbors.
+fn is_redundant(image: &BinImage, x: usize, y: usize) -> bool {
+    let n = image.get_neighbors(x, y);
+
+    n.count() >= 2 && n.is_simple()
+}
+
+/// Finding every pixel which keeps the skeleton thicker than one pixel
+pub fn check_thickness(image: &BinImage) -> ThicknessReport {
+    let mut redundant = vec![];
+    let mut foreground = 0;
+    let mut blocks = 0;
+
+    for (x, y, n) in image.neighborhoods() {
+        if !n.centre() {
+            continue;
+        }
+        foreground += 1;
+
+        if is_redundant(image, x, y) {
+            redundant.push((x, y));
+        }
+
+        if n.east() && n.south_east() && n.south() {
+            blocks += 1;
+        }
+    }
+
+    ThicknessReport {
+        redundant,
+        foreground,
+        blocks,
+    }
+}
+
+/// Removing redundant pixels one by one until the image is one pixel thick.
+/// Returns the number of removed pixels.
+pub fn remove_redundant_pixels(image: &mut BinImage) -> usize {
+    let bounds = (0, 0, image.get_width(), image.get_height());
+    remove_redundant_pixels_where(image, bounds, |_, _| true)
+}
+
+// Removing only the redundant pixels inside `(x0, y0, x1, y1)` with exclusive
+// ends which are accepted by the filter. Pixels outside of the bounds are not
+// visited.
+pub(crate) fn remove_redundant_pixels_where<F: Fn(usize, usize) -> bool>(
+    image: &mut BinImage,
+    (x0, y0, x1, y1): (usize, usize, usize, usize),
+    filter: F,
+) -> usize {
+    let mut removed = 0;
+    let mut changed = true;
+
+    while changed {
+        changed = false;
+
+        // Pixels are checked against the image as updated so far
+        for y in y0..y1 {
+            for x in x0..x1 {
+                if filter(x, y) && is_redundant(image, x, y) {
+                    image.set_value(x, y, false).unwrap();
+                    removed += 1;
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    removed
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::topology::verify_topology;
+
+    #[test]
+    fn test_check_thickness() {
+        let img = BinImage::try_from(PathBuf::from("./test_data/b_char_thinned.txt")).unwrap();
+
+        let report = check_thickness(&img);
+
+        assert_eq!(report.foreground, 75);
+        assert_eq!(report.blocks, 0);
+        assert!(report.redundant.contains(&(22, 16)));
+        assert!(report.redundant.contains(&(5, 3)));
+        assert!(!report.redundant.contains(&(10, 3)));
+        assert!(!report.is_unit_thick());
+
+        let block = BinImage::try_from(vec![
+            vec![false, false, false, false],
+            vec![false, true, true, false],
+            vec![false, true, true, false],
+        ])
+        .unwrap();
+        let report = check_thickness(&block);
+        assert_eq!(report.blocks, 1);
+        assert_eq!(report.redundant.len(), 4);
+    }
+
+    #[test]
+    fn test_remove_redundant_pixels() {
+        let img = BinImage::try_from(PathBuf::from("./test_data/b_char_thinned.txt")).unwrap();
+
+        let mut cleaned = img.clone();
+        let removed = remove_redundant_pixels(&mut cleaned);
+
+        assert!(removed > 0);
+        assert!(check_thickness(&cleaned).is_unit_thick());
+        assert!(verify_topology(&img, &cleaned).unwrap().is_preserved());
+
+        let mut block = BinImage::new(3, 3, false);
+        block.set_value(0, 0, true).unwrap();
+        block.set_value(1, 0, true).unwrap();
+        block.set_value(0, 1, true).unwrap();
+        block.set_value(1, 1, true).unwrap();
+
+        assert_eq!(remove_redundant_pixels(&mut block), 2);
+        assert!(check_thickness(&block).is_unit_thick());
+
+        // Only the pixels inside the bounds are removed
+        let mut left = img.clone();
+        let bounds = (0, 0, 10, img.get_height());
+        assert!(remove_redundant_pixels_where(&mut left, bounds, |_, _| true) > 0);
+        assert!(left.get_value(5, 3).is_ok_and(|val| !val));
+        for (x, y, val) in img.iter().filter(|(x, _, _)| *x >= 10) {
+            assert_eq!(left.get_value(x, y).unwrap(), val);
+        }
+    }
+}
@@ -0,0 +1,470 @@
+// Thinning very large images tile by tile
+use crate::bin_image::BinImage;
+use crate::common::keep_components;
+#[cfg(all(test, not(feature = "improved_ysc_whh")))]
+use crate::default::imgthin;
+#[cfg(not(feature = "improved_ysc_whh"))]
+use crate::default::{imgthin_options, CONDITIONS};
+#[cfg(all(test, feature = "improved_ysc_whh"))]
+use crate::ysc_whh::imgthin;
+#[cfg(feature = "improved_ysc_whh")]
+use crate::ysc_whh::{imgthin_options, CONDITIONS};
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind};
+use std::ops::Range;
+#[cfg(test)]
+use std::path::PathBuf;
+
+/// Storage of an image that is read a tile at a time
+pub trait TileSource {
+    /// Width and height of the whole image
+    fn dimensions(&self) -> (usize, usize);
+
+    /// Reading a rectangle of the image. The rectangle is always inside the
+    /// image.
+    fn read_tile(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> Result<BinImage, Error>;
+}
+
+/// Storage of an image that is written a tile at a time
+pub trait TileSink {
+    /// Writing a rectangle of the image with the top left corner at `x` and
+    /// `y`. The rectangle is always inside the image.
+    fn write_tile(&mut self, x: usize, y: usize, tile: &BinImage) -> Result<(), Error>;
+}
+
+impl TileSource for BinImage {
+    fn dimensions(&self) -> (usize, usize) {
+        (self.get_width(), self.get_height())
+    }
+
+    fn read_tile(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> Result<BinImage, Error> {
+        if x + width > self.get_width() || y + height > self.get_height() {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tile is out of bounds"));
+        }
+
+        let pixels: Vec<Vec<bool>> = self.get_pixels()[y..y + height]
+            .iter()
+            .map(|row| row[x..x + width].to_vec())
+            .collect();
+
+        BinImage::try_from(pixels)
+    }
+}
+
+impl TileSink for BinImage {
+    fn write_tile(&mut self, x: usize, y: usize, tile: &BinImage) -> Result<(), Error> {
+        if x + tile.get_width() > self.get_width() || y + tile.get_height() > self.get_height() {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tile is out of bounds"));
+        }
+
+        for (ty, row) in tile.get_pixels().iter().enumerate() {
+            for (tx, val) in row.iter().enumerate() {
+                self.set_value(x + tx, y + ty, *val)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TileOptions {
+    pub tile_width: usize,
+    pub tile_height: usize,
+    /// Pixels read around every tile. This is also the number of
+    /// sub-iterations run on a tile before moving to the next one, so a
+    /// bigger halo means fewer passes over the image. Must be at least 2.
+    pub halo: usize,
+}
+
+impl Default for TileOptions {
+    fn default() -> TileOptions {
+        TileOptions {
+            tile_width: 512,
+            tile_height: 512,
+            halo: 16,
+        }
+    }
+}
+
+// Rectangle of a tile as `(x, y, width, height)`
+type Rect = (usize, usize, usize, usize);
+
+// Pixels of a tile core at the start of a pass, one bit for every pixel
+struct CoreBits {
+    core: Rect,
+    bits: Vec<u64>,
+}
+
+impl CoreBits {
+    // Bits of the core in a buffer with the top left corner at `x0` and `y0`
+    fn new(buffer: &BinImage, core: Rect, x0: usize, y0: usize) -> CoreBits {
+        let mut bits = vec![0; (core.2 * core.3).div_ceil(64)];
+        for y in 0..core.3 {
+            for x in 0..core.2 {
+                if buffer.get_value(core.0 - x0 + x, core.1 - y0 + y).unwrap() {
+                    let i = y * core.2 + x;
+                    bits[i / 64] |= 1 << (i % 64);
+                }
+            }
+        }
+
+        CoreBits { core, bits }
+    }
+
+    // Setting the foreground pixels of the core which are inside a buffer
+    // with the top left corner at `x0` and `y0`
+    fn restore(&self, buffer: &mut BinImage, x0: usize, y0: usize) -> Result<(), Error> {
+        let (cx, cy, width, height) = self.core;
+        for y in cy.max(y0)..(cy + height).min(y0 + buffer.get_height()) {
+            for x in cx.max(x0)..(cx + width).min(x0 + buffer.get_width()) {
+                let i = (y - cy) * width + x - cx;
+                if self.bits[i / 64] >> (i % 64) & 1 == 1 {
+                    buffer.set_value(x - x0, y - y0, true)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Layout of the tiles of an image
+struct Tiling {
+    width: usize,
+    height: usize,
+    tile_width: usize,
+    tile_height: usize,
+    halo: usize,
+}
+
+impl Tiling {
+    fn columns(&self) -> usize {
+        self.width.div_ceil(self.tile_width)
+    }
+
+    fn rows(&self) -> usize {
+        self.height.div_ceil(self.tile_height)
+    }
+
+    fn core(&self, column: usize, row: usize) -> Rect {
+        let x = column * self.tile_width;
+        let y = row * self.tile_height;
+        (
+            x,
+            y,
+            self.tile_width.min(self.width - x),
+            self.tile_height.min(self.height - y),
+        )
+    }
+
+    // The core with its halo, clipped to the image
+    fn with_halo(&self, core: Rect) -> Rect {
+        let x0 = core.0.saturating_sub(self.halo);
+        let y0 = core.1.saturating_sub(self.halo);
+        let x1 = (core.0 + core.2 + self.halo).min(self.width);
+        let y1 = (core.1 + core.3 + self.halo).min(self.height);
+        (x0, y0, x1 - x0, y1 - y0)
+    }
+
+    // Columns and rows of the tiles within `reach` pixels of a rectangle
+    fn around(&self, rect: Rect, reach: usize) -> (Range<usize>, Range<usize>) {
+        let column0 = rect.0.saturating_sub(reach) / self.tile_width;
+        let column1 = ((rect.0 + rect.2 - 1 + reach) / self.tile_width).min(self.columns() - 1);
+        let row0 = rect.1.saturating_sub(reach) / self.tile_height;
+        let row1 = ((rect.1 + rect.3 - 1 + reach) / self.tile_height).min(self.rows() - 1);
+        (column0..column1 + 1, row0..row1 + 1)
+    }
+}
+
+// Reading a rectangle as it was at the start of the pass. The first pass
+// reads the source. Later passes read the sink, and put back the pixels of
+// the tiles which were already thinned in this pass.
+fn read_before<S: TileSource, K: TileSource>(
+    source: &mut S,
+    sink: &mut K,
+    first_pass: bool,
+    tiling: &Tiling,
+    before: &[Vec<Option<CoreBits>>],
+    rect: Rect,
+) -> Result<BinImage, Error> {
+    if first_pass {
+        return source.read_tile(rect.0, rect.1, rect.2, rect.3);
+    }
+
+    let mut buffer = sink.read_tile(rect.0, rect.1, rect.2, rect.3)?;
+    let (columns, rows) = tiling.around(rect, 0);
+    for row in rows {
+        for bits in before[row][columns.clone()].iter().flatten() {
+            bits.restore(&mut buffer, rect.0, rect.1)?;
+        }
+    }
+
+    Ok(buffer)
+}
+
+// Running `count` sub-iterations from `s` on a tile with its halo, and
+// calling `deleted` with the sub-iteration in the pass and the image
+// coordinates of every pixel deleted in the core.
+//
+// Pixels outside of the image are background like in `imgthin`, but the
+// missing neighbors past the halo spoil one more pixel of the halo with
+// every sub-iteration. The core stays exact for `halo` sub-iterations.
+fn thin_tile<F: FnMut(usize, usize, usize)>(
+    buffer: &mut BinImage,
+    tiling: &Tiling,
+    core: Rect,
+    s: usize,
+    count: usize,
+    mut deleted: F,
+) -> Result<(), Error> {
+    let options = imgthin_options();
+    let bounds = tiling.with_halo(core);
+
+    for i in 0..count {
+        let mut m = vec![];
+        for y in 0..buffer.get_height() {
+            for x in 0..buffer.get_width() {
+                if (CONDITIONS.is_deletable)(buffer, s + i, x, y, &options) {
+                    m.push((x, y));
+                }
+            }
+        }
+
+        // Components are kept like in `imgthin`, unless they may go on past
+        // the halo
+        if CONDITIONS.keep_components {
+            keep_components(buffer, &mut m, |x, y| {
+                (x == 0 && bounds.0 > 0)
+                    || (y == 0 && bounds.1 > 0)
+                    || (x + 1 == bounds.2 && bounds.0 + bounds.2 < tiling.width)
+                    || (y + 1 == bounds.3 && bounds.1 + bounds.3 < tiling.height)
+            });
+        }
+
+        for (x, y) in m {
+            buffer.set_value(x, y, false)?;
+
+            let (gx, gy) = (x + bounds.0, y + bounds.1);
+            if (core.0..core.0 + core.2).contains(&gx) && (core.1..core.1 + core.3).contains(&gy) {
+                deleted(i, gx, gy);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Thinning an image tile by tile. The result is the same as thinning the
+/// whole image with `imgthin`, but only one tile with its halo is kept in
+/// memory, along with one bit for every pixel of the tiles thinned during
+/// the current pass.
+///
+/// Tiles are read from `source` on the first pass and written to `sink`.
+/// Later passes read their tiles back from `sink`, so the thinned image is
+/// in `sink` when this returns.
+pub fn imgthin_tiled<S, K>(source: &mut S, sink: &mut K, options: &TileOptions) -> Result<(), Error>
+where
+    S: TileSource,
+    K: TileSource + TileSink,
+{
+    if options.tile_width == 0 || options.tile_height == 0 || options.halo < 2 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Tiles must not be empty and the halo must be at least 2",
+        ));
+    }
+
+    let (width, height) = source.dimensions();
+    if sink.dimensions() != (width, height) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Source and sink have different dimensions",
+        ));
+    }
+    if width == 0 || height == 0 {
+        return Ok(());
+    }
+
+    let tiling = Tiling {
+        width,
+        height,
+        tile_width: options.tile_width,
+        tile_height: options.tile_height,
+        halo: options.halo,
+    };
+    let halo = options.halo;
+    let (columns, rows) = (tiling.columns(), tiling.rows());
+
+    // Tiles to thin in the next pass. A tile which had no deletions within
+    // `halo + 1` pixels in the last pass cannot change any more, as a
+    // change takes more than `halo` sub-iterations to reach it.
+    let mut active = vec![vec![true; columns]; rows];
+    let mut first_pass = true;
+    let mut s = 0;
+
+    loop {
+        // Cores of the tiles thinned in this pass as they were before it
+        let mut before: Vec<Vec<Option<CoreBits>>> = (0..rows)
+            .map(|_| (0..columns).map(|_| None).collect())
+            .collect();
+        let mut next_active = vec![vec![false; columns]; rows];
+        let mut counts = vec![0; halo];
+
+        for row in 0..rows {
+            for column in (0..columns).filter(|column| active[row][*column]) {
+                let core = tiling.core(column, row);
+                let bounds = tiling.with_halo(core);
+
+                let mut buffer = read_before(source, sink, first_pass, &tiling, &before, bounds)?;
+                if !first_pass {
+                    before[row][column] = Some(CoreBits::new(&buffer, core, bounds.0, bounds.1));
+                }
+
+                // Bounding rectangle of the deletions as `(x0, y0, x1, y1)`
+                let mut changed: Option<(usize, usize, usize, usize)> = None;
+                thin_tile(&mut buffer, &tiling, core, s, halo, |i, x, y| {
+                    counts[i] += 1;
+                    changed = Some(match changed {
+                        Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+                        None => (x, y, x, y),
+                    });
+                })?;
+
+                if let Some((x0, y0, x1, y1)) = changed {
+                    let (columns, rows) =
+                        tiling.around((x0, y0, x1 - x0 + 1, y1 - y0 + 1), halo + 1);
+                    for next_row in next_active[rows].iter_mut() {
+                        next_row[columns.clone()].fill(true);
+                    }
+                }
+
+                let core_buffer =
+                    buffer.read_tile(core.0 - bounds.0, core.1 - bounds.1, core.2, core.3)?;
+                sink.write_tile(core.0, core.1, &core_buffer)?;
+            }
+        }
+
+        // Thinning may stop in the middle of the pass. The tiles of the pass
+        // are thinned again from the state before it, up to the stop.
+        if let Some(stop) = (0..halo).find(|i| (CONDITIONS.is_converged)(s + i, counts[*i])) {
+            for (row, active_row) in active.iter().enumerate() {
+                for column in (0..columns).filter(|column| active_row[*column]) {
+                    let core = tiling.core(column, row);
+                    let bounds = tiling.with_halo(core);
+
+                    let mut buffer =
+                        read_before(source, sink, first_pass, &tiling, &before, bounds)?;
+                    thin_tile(&mut buffer, &tiling, core, s, stop + 1, |_, _, _| {})?;
+
+                    let core_buffer =
+                        buffer.read_tile(core.0 - bounds.0, core.1 - bounds.1, core.2, core.3)?;
+                    sink.write_tile(core.0, core.1, &core_buffer)?;
+                }
+            }
+
+            return Ok(());
+        }
+
+        active = next_active;
+        first_pass = false;
+        s += halo;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_same_as_imgthin(img: &BinImage, options: &TileOptions) {
+        let expected = imgthin(img.get_pixels().clone()).unwrap();
+
+        let mut source = img.clone();
+        let mut sink = BinImage::new(img.get_width(), img.get_height(), false);
+        imgthin_tiled(&mut source, &mut sink, options).unwrap();
+
+        crate::assert_bin_image_eq!(BinImage::try_from(expected).unwrap(), sink);
+    }
+
+    #[test]
+    fn test_tiled_char_b() {
+        let img = BinImage::try_from(PathBuf::from("./test_data/b_char.txt")).unwrap();
+
+        for (tile_width, tile_height, halo) in [(4, 4, 2), (5, 7, 3), (8, 8, 2), (100, 100, 16)] {
+            assert_same_as_imgthin(
+                &img,
+                &TileOptions {
+                    tile_width,
+                    tile_height,
+                    halo,
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn test_tiled_blobs() {
+        // Pseudo random overlapping rectangles
+        let mut img = BinImage::new(61, 47, false);
+        let mut seed: u32 = 12345;
+        let mut next = |max: usize| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) as usize % max
+        };
+        for _ in 0..25 {
+            let (x, y) = (next(55), next(41));
+            let (w, h) = (next(14) + 1, next(12) + 1);
+            for py in y..(y + h).min(47) {
+                for px in x..(x + w).min(61) {
+                    img.set_value(px, py, true).unwrap();
+                }
+            }
+        }
+
+        for (tile_width, tile_height, halo) in [(6, 6, 2), (10, 9, 4), (16, 16, 3)] {
+            assert_same_as_imgthin(
+                &img,
+                &TileOptions {
+                    tile_width,
+                    tile_height,
+                    halo,
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn test_tiled_in_place() {
+        let img = BinImage::try_from(PathBuf::from("./test_data/b_char.txt")).unwrap();
+        let expected = imgthin(img.get_pixels().clone()).unwrap();
+
+        let mut source = img.clone();
+        let mut sink = img.clone();
+        imgthin_tiled(&mut source, &mut sink, &TileOptions::default()).unwrap();
+        assert_eq!(sink.get_pixels(), &expected);
+
+        let invalid = TileOptions {
+            halo: 1,
+            ..TileOptions::default()
+        };
+        assert!(imgthin_tiled(&mut source, &mut sink, &invalid).is_err());
+        assert!(imgthin_tiled(
+            &mut source,
+            &mut BinImage::new(1, 1, false),
+            &TileOptions::default()
+        )
+        .is_err());
+    }
+}
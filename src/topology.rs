@@ -0,0 +1,279 @@
+// Topology checks between an image and its thinned skeleton
+use crate::bin_image::BinImage;
+#[cfg(test)]
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind};
+#[cfg(test)]
+use std::path::PathBuf;
+
+/// A change in topology found by `verify_topology`. Components are
+/// identified by their first pixel in raster order.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TopologyIssue {
+    /// An original component has no pixels left in the thinned image
+    Vanished { component: (usize, usize) },
+    /// An original component was split into `parts` thinned components
+    Broken {
+        component: (usize, usize),
+        parts: usize,
+    },
+    /// A thinned component joins several original components
+    Merged { components: Vec<(usize, usize)> },
+    /// A thinned component lies entirely on the original background
+    Appeared { component: (usize, usize) },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TopologyReport {
+    /// 8-connected foreground components of the original image
+    pub original_components: usize,
+    /// 8-connected foreground components of the thinned image
+    pub thinned_components: usize,
+    /// 4-connected background holes of the original image
+    pub original_holes: usize,
+    /// 4-connected background holes of the thinned image
+    pub thinned_holes: usize,
+    pub issues: Vec<TopologyIssue>,
+}
+
+impl TopologyReport {
+    pub fn original_euler_number(&self) -> isize {
+        self.original_components as isize - self.original_holes as isize
+    }
+
+    pub fn thinned_euler_number(&self) -> isize {
+        self.thinned_components as isize - self.thinned_holes as isize
+    }
+
+    /// Whether the thinned image keeps every component and every hole
+    pub fn is_preserved(&self) -> bool {
+        self.issues.is_empty()
+            && self.original_components == self.thinned_components
+            && self.original_holes == self.thinned_holes
+    }
+}
+
+// Connected component labels. Label 0 is used for pixels which are not part
+// of any component.
+pub(crate) struct Labels {
+    pub(crate) labels: Vec<Vec<usize>>,
+    // First pixel of every component in raster order, indexed by label - 1
+    pub(crate) seeds: Vec<(usize, usize)>,
+}
+
+pub(crate) fn label_components(image: &BinImage, value: bool, eight_connected: bool) -> Labels {
+    let width = image.get_width();
+    let height = image.get_height();
+    let pixels = image.get_pixels();
+
+    let mut labels = vec![vec![0; width]; height];
+    let mut seeds = vec![];
+    let mut stack = vec![];
+
+    for y in 0..height {
+        for x in 0..width {
+            if pixels[y][x] != value || labels[y][x] != 0 {
+                continue;
+            }
+
+            seeds.push((x, y));
+            let label = seeds.len();
+            labels[y][x] = label;
+            stack.push((x, y));
+
+            while let Some((cx, cy)) = stack.pop() {
+                for ny in cy.saturating_sub(1)..=(cy + 1).min(height - 1) {
+                    for nx in cx.saturating_sub(1)..=(cx + 1).min(width - 1) {
+                        let diagonal = nx != cx && ny != cy;
+                        if (diagonal && !eight_connected)
+                            || pixels[ny][nx] != value
+                            || labels[ny][nx] != 0
+                        {
+                            continue;
+                        }
+                        labels[ny][nx] = label;
+                        stack.push((nx, ny));
+                    }
+                }
+            }
+        }
+    }
+
+    Labels { labels, seeds }
+}
+
+pub(crate) fn count_holes(image: &BinImage) -> usize {
+    let background = label_components(image, false, false);
+    let width = image.get_width();
+    let height = image.get_height();
+
+    // Background components touching the border are not holes
+    let mut outside = vec![false; background.seeds.len() + 1];
+    for x in 0..width {
+        outside[background.labels[0][x]] = true;
+        outside[background.labels[height - 1][x]] = true;
+    }
+    for y in 0..height {
+        outside[background.labels[y][0]] = true;
+        outside[background.labels[y][width - 1]] = true;
+    }
+
+    outside.iter().skip(1).filter(|o| !**o).count()
+}
+
+/// Counting 8-connected foreground components minus 4-connected holes
+pub fn euler_number(image: &BinImage) -> isize {
+    if image.get_width() == 0 {
+        return 0;
+    }
+    label_components(image, true, true).seeds.len() as isize - count_holes(image) as isize
+}
+
+/// Comparing the topology of an image with its thinned version. Both images
+/// must have the same dimensions.
+pub fn verify_topology(original: &BinImage, thinned: &BinImage) -> Result<TopologyReport, Error> {
+    if original.get_width() != thinned.get_width() || original.get_height() != thinned.get_height()
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Images have different dimensions",
+        ));
+    }
+
+    if original.get_width() == 0 {
+        return Ok(TopologyReport {
+            original_components: 0,
+            thinned_components: 0,
+            original_holes: 0,
+            thinned_holes: 0,
+            issues: vec![],
+        });
+    }
+
+    let original_labels = label_components(original, true, true);
+    let thinned_labels = label_components(thinned, true, true);
+
+    // Original components covered by every thinned component
+    let mut covered: Vec<Vec<usize>> = vec![vec![]; thinned_labels.seeds.len()];
+    for (y, row) in thinned_labels.labels.iter().enumerate() {
+        for (x, thinned_label) in row.iter().enumerate() {
+            let original_label = original_labels.labels[y][x];
+            if *thinned_label == 0 || original_label == 0 {
+                continue;
+            }
+            let covered = &mut covered[thinned_label - 1];
+            if !covered.contains(&original_label) {
+                covered.push(original_label);
+            }
+        }
+    }
+
+    let mut issues = vec![];
+    let mut parts = vec![0; original_labels.seeds.len()];
+
+    for (i, covered) in covered.iter().enumerate() {
+        match covered.len() {
+            0 => issues.push(TopologyIssue::Appeared {
+                component: thinned_labels.seeds[i],
+            }),
+            1 => {}
+            _ => issues.push(TopologyIssue::Merged {
+                components: covered
+                    .iter()
+                    .map(|label| original_labels.seeds[label - 1])
+                    .collect(),
+            }),
+        }
+
+        for label in covered.iter() {
+            parts[label - 1] += 1;
+        }
+    }
+
+    for (i, parts) in parts.iter().enumerate() {
+        let component = original_labels.seeds[i];
+        match parts {
+            0 => issues.push(TopologyIssue::Vanished { component }),
+            1 => {}
+            _ => issues.push(TopologyIssue::Broken {
+                component,
+                parts: *parts,
+            }),
+        }
+    }
+
+    Ok(TopologyReport {
+        original_components: original_labels.seeds.len(),
+        thinned_components: thinned_labels.seeds.len(),
+        original_holes: count_holes(original),
+        thinned_holes: count_holes(thinned),
+        issues,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_euler_number() {
+        let img = BinImage::try_from(PathBuf::from("./test_data/b_char.txt")).unwrap();
+        assert_eq!(euler_number(&img), -1);
+
+        let thinned = BinImage::try_from(PathBuf::from("./test_data/b_char_thinned.txt")).unwrap();
+        assert_eq!(euler_number(&thinned), -1);
+    }
+
+    #[test]
+    fn test_verify_topology_preserved() {
+        let img = BinImage::try_from(PathBuf::from("./test_data/b_char.txt")).unwrap();
+        let thinned = BinImage::try_from(PathBuf::from("./test_data/b_char_thinned.txt")).unwrap();
+
+        let report = verify_topology(&img, &thinned).unwrap();
+
+        assert_eq!(report.original_components, 1);
+        assert_eq!(report.original_holes, 2);
+        assert!(report.is_preserved());
+    }
+
+    #[test]
+    fn test_verify_topology_issues() {
+        let img = BinImage::try_from(vec![
+            vec![true, true, true, false, true],
+            vec![true, false, true, false, true],
+            vec![true, true, true, false, true],
+        ])
+        .unwrap();
+
+        let thinned = BinImage::try_from(vec![
+            vec![true, false, true, false, false],
+            vec![true, false, true, false, false],
+            vec![true, true, true, true, true],
+        ])
+        .unwrap();
+
+        let report = verify_topology(&img, &thinned).unwrap();
+
+        assert_eq!(report.original_holes, 1);
+        assert_eq!(report.thinned_holes, 0);
+        assert_eq!(
+            report.issues,
+            vec![TopologyIssue::Merged {
+                components: vec![(0, 0), (4, 0)]
+            }]
+        );
+        assert!(!report.is_preserved());
+
+        let empty = BinImage::new(5, 3, false);
+        let report = verify_topology(&img, &empty).unwrap();
+        assert_eq!(
+            report.issues,
+            vec![
+                TopologyIssue::Vanished { component: (0, 0) },
+                TopologyIssue::Vanished { component: (4, 0) }
+            ]
+        );
+
+        assert!(verify_topology(&img, &BinImage::new(4, 3, false)).is_err());
+    }
+}
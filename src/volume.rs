@@ -0,0 +1,332 @@
+// Directional thinning of binary volumes
+use crate::bin_volume::BinVolume;
+use std::collections::HashMap;
+
+/// Kind of skeleton made by `thin_volume`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VolumeSkeleton {
+    /// Thinning down to centrelines one voxel thick. Only the ends of
+    /// curves are kept.
+    Curve,
+    /// Thinning down to the medial surface. Voxels on surfaces one voxel
+    /// thick are kept, which keeps plates as plates.
+    Surface,
+}
+
+// The six border directions, thinned one after another in every iteration:
+// up, down, north, south, east and west
+const DIRECTIONS: [(isize, isize, isize); 6] = [
+    (0, -1, 0),
+    (0, 1, 0),
+    (0, 0, -1),
+    (0, 0, 1),
+    (1, 0, 0),
+    (-1, 0, 0),
+];
+
+// Position of an offset in the 3 by 3 by 3 neighborhood. The centre is 13.
+fn position(dx: isize, dy: isize, dz: isize) -> usize {
+    ((dz + 1) * 9 + (dy + 1) * 3 + (dx + 1)) as usize
+}
+
+fn offset(position: usize) -> (isize, isize, isize) {
+    (
+        (position % 3) as isize - 1,
+        (position / 3 % 3) as isize - 1,
+        (position / 9) as isize - 1,
+    )
+}
+
+// 3 by 3 by 3 neighborhood of a voxel as a bit mask, see `position`
+fn get_neighborhood(volume: &BinVolume, x: usize, y: usize, z: usize) -> u32 {
+    (0..27)
+        .filter(|p| {
+            let (dx, dy, dz) = offset(*p);
+            volume.get_offset_value(x, y, z, dx, dy, dz)
+        })
+        .fold(0, |mask, p| mask | (1 << p))
+}
+
+// Number of connected components of the voxels in `voxels` which include a
+// voxel of `seeds`. With `six_connected`, only voxels sharing a face are
+// connected, otherwise voxels sharing a corner are connected too.
+fn count_components(voxels: u32, seeds: u32, six_connected: bool) -> usize {
+    let mut unvisited = voxels;
+    let mut components = 0;
+
+    for seed in 0..27 {
+        if seeds & unvisited & (1 << seed) == 0 {
+            continue;
+        }
+
+        components += 1;
+        unvisited &= !(1 << seed);
+        let mut stack = vec![seed];
+
+        while let Some(p) = stack.pop() {
+            let (px, py, pz) = offset(p);
+            for q in 0..27 {
+                if unvisited & (1 << q) == 0 {
+                    continue;
+                }
+                let (qx, qy, qz) = offset(q);
+                let (dx, dy, dz) = ((px - qx).abs(), (py - qy).abs(), (pz - qz).abs());
+                let adjacent = if six_connected {
+                    dx + dy + dz == 1
+                } else {
+                    dx.max(dy).max(dz) == 1
+                };
+                if adjacent {
+                    unvisited &= !(1 << q);
+                    stack.push(q);
+                }
+            }
+        }
+    }
+
+    components
+}
+
+// Whether the centre of a neighborhood is a simple point for 26-connected
+// foreground and 6-connected background. The foreground neighbors must form a
+// single component, and so must the background in the 18-neighborhood that
+// touches a face of the centre.
+fn is_simple(neighborhood: u32) -> bool {
+    let centre = 1 << 13;
+    let mut n18 = 0;
+    let mut n6 = 0;
+    for p in 0..27 {
+        let (dx, dy, dz) = offset(p);
+        let distance = dx.abs() + dy.abs() + dz.abs();
+        if distance == 1 || distance == 2 {
+            n18 |= 1 << p;
+        }
+        if distance == 1 {
+            n6 |= 1 << p;
+        }
+    }
+
+    let foreground = neighborhood & !centre & ((1 << 27) - 1);
+    let background = !neighborhood & n18;
+
+    count_components(foreground, foreground, false) == 1
+        && count_components(background, n6, true) == 1
+}
+
+// Whether a voxel is kept as an end of the skeleton
+fn is_end(neighborhood: u32, mode: VolumeSkeleton) -> bool {
+    let neighbors = (neighborhood & !(1 << 13)).count_ones();
+
+    match mode {
+        VolumeSkeleton::Curve => neighbors <= 1,
+        VolumeSkeleton::Surface => {
+            neighbors <= 1
+                || [(1, 0, 0), (0, 1, 0), (0, 0, 1)]
+                    .iter()
+                    .any(|(dx, dy, dz)| {
+                        neighborhood & (1 << position(*dx, *dy, *dz)) == 0
+                            && neighborhood & (1 << position(-dx, -dy, -dz)) == 0
+                    })
+        }
+    }
+}
+
+/// Thinning a volume to its curve skeleton or medial surface, keeping the
+/// topology for 26-connected foreground and 6-connected background.
+///
+/// Every iteration peels the border voxels in the six face directions one
+/// after another, like the algorithm of Lee, Kashyap and Chu. Voxels found
+/// in a direction are deleted one by one, and every voxel is checked to still
+/// be simple before it is deleted.
+pub fn thin_volume(volume: &BinVolume, mode: VolumeSkeleton) -> BinVolume {
+    let mut volume = volume.clone();
+
+    // Like the tables of the improved algorithm, but filled on demand, as
+    // there are 2^26 neighborhoods
+    let mut table: HashMap<u32, bool> = HashMap::new();
+    let mut is_simple_cached = |neighborhood: u32| {
+        *table
+            .entry(neighborhood)
+            .or_insert_with(|| is_simple(neighborhood))
+    };
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+
+        for (dx, dy, dz) in DIRECTIONS.iter() {
+            let mut candidates = vec![];
+
+            for z in 0..volume.get_depth() {
+                for y in 0..volume.get_height() {
+                    for x in 0..volume.get_width() {
+                        if !volume.get_value(x, y, z).unwrap()
+                            || volume.get_offset_value(x, y, z, *dx, *dy, *dz)
+                        {
+                            continue;
+                        }
+
+                        let neighborhood = get_neighborhood(&volume, x, y, z);
+                        if !is_end(neighborhood, mode) && is_simple_cached(neighborhood) {
+                            candidates.push((x, y, z));
+                        }
+                    }
+                }
+            }
+
+            // Ends are only found before deleting, as the deletions in this
+            // direction would make ends of the voxels next to them
+            for (x, y, z) in candidates {
+                if is_simple_cached(get_neighborhood(&volume, x, y, z)) {
+                    volume.set_value(x, y, z, false).unwrap();
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    volume
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Connected components of the voxels with the value `foreground`. The
+    // foreground is 26-connected and the background 6-connected.
+    fn count_volume_components(volume: &BinVolume, foreground: bool) -> usize {
+        let mut seen = BinVolume::new(
+            volume.get_width(),
+            volume.get_height(),
+            volume.get_depth(),
+            false,
+        );
+        let mut components = 0;
+        let is_value = |x: usize, y: usize, z: usize, dx: isize, dy: isize, dz: isize| {
+            let (nx, ny, nz) = (x as isize + dx, y as isize + dy, z as isize + dz);
+            nx >= 0
+                && ny >= 0
+                && nz >= 0
+                && volume
+                    .get_value(nx as usize, ny as usize, nz as usize)
+                    .is_ok_and(|v| v == foreground)
+        };
+
+        for z in 0..volume.get_depth() {
+            for y in 0..volume.get_height() {
+                for x in 0..volume.get_width() {
+                    if !is_value(x, y, z, 0, 0, 0) || seen.get_value(x, y, z).unwrap() {
+                        continue;
+                    }
+                    components += 1;
+                    seen.set_value(x, y, z, true).unwrap();
+                    let mut stack = vec![(x, y, z)];
+                    while let Some((cx, cy, cz)) = stack.pop() {
+                        for p in 0..27 {
+                            let (dx, dy, dz) = offset(p);
+                            if (!foreground && dx.abs() + dy.abs() + dz.abs() != 1)
+                                || !is_value(cx, cy, cz, dx, dy, dz)
+                                || seen.get_offset_value(cx, cy, cz, dx, dy, dz)
+                            {
+                                continue;
+                            }
+                            let n = (
+                                (cx as isize + dx) as usize,
+                                (cy as isize + dy) as usize,
+                                (cz as isize + dz) as usize,
+                            );
+                            seen.set_value(n.0, n.1, n.2, true).unwrap();
+                            stack.push(n);
+                        }
+                    }
+                }
+            }
+        }
+
+        components
+    }
+
+    fn cuboid(width: usize, height: usize, depth: usize) -> BinVolume {
+        let mut volume = BinVolume::new(width + 2, height + 2, depth + 2, false);
+        for z in 1..=depth {
+            for y in 1..=height {
+                for x in 1..=width {
+                    volume.set_value(x, y, z, true).unwrap();
+                }
+            }
+        }
+        volume
+    }
+
+    #[test]
+    fn test_is_simple() {
+        let centre = 1 << 13;
+        // An isolated voxel and an interior voxel are not simple
+        assert!(!is_simple(centre));
+        assert!(!is_simple((1 << 27) - 1));
+        // The end of a line is simple, the middle is not
+        assert!(is_simple(centre | 1 << position(1, 0, 0)));
+        assert!(!is_simple(
+            centre | 1 << position(1, 0, 0) | 1 << position(-1, 0, 0)
+        ));
+        // A voxel on the face of a cube is simple
+        let face = (0..27)
+            .filter(|p| offset(*p).2 >= 0)
+            .fold(0, |mask, p| mask | 1 << p);
+        assert!(is_simple(face));
+    }
+
+    #[test]
+    fn test_curve_skeleton() {
+        // A square rod along the z axis is thinned to a single line
+        let volume = cuboid(3, 3, 9);
+
+        let skeleton = thin_volume(&volume, VolumeSkeleton::Curve);
+
+        assert_eq!(count_volume_components(&skeleton, true), 1);
+        for z in 0..skeleton.get_depth() {
+            let count = skeleton.get_voxels()[z]
+                .iter()
+                .flatten()
+                .filter(|v| **v)
+                .count();
+            assert!(count <= 1, "slice {} has {} voxels", z, count);
+        }
+        assert!(skeleton.count_foreground() >= 5);
+    }
+
+    #[test]
+    fn test_surface_skeleton() {
+        // A thick plate is thinned to a surface one voxel thick
+        let volume = cuboid(9, 9, 3);
+
+        let curve = thin_volume(&volume, VolumeSkeleton::Curve);
+        let surface = thin_volume(&volume, VolumeSkeleton::Surface);
+
+        assert_eq!(count_volume_components(&surface, true), 1);
+        assert!(surface.count_foreground() > curve.count_foreground());
+        for y in 0..surface.get_height() {
+            for x in 0..surface.get_width() {
+                let count = (0..surface.get_depth())
+                    .filter(|z| surface.get_value(x, y, *z).unwrap())
+                    .count();
+                assert!(count <= 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hollow_cube() {
+        // A hollow cube keeps its cavity, so the skeleton is a closed surface
+        // around it
+        let mut volume = cuboid(5, 5, 5);
+        volume.set_value(3, 3, 3, false).unwrap();
+        assert_eq!(count_volume_components(&volume, false), 2);
+
+        let skeleton = thin_volume(&volume, VolumeSkeleton::Curve);
+
+        assert_eq!(count_volume_components(&skeleton, true), 1);
+        assert_eq!(count_volume_components(&skeleton, false), 2);
+        assert!(!skeleton.get_value(3, 3, 3).unwrap());
+    }
+}
@@ -1,22 +1,31 @@
 use crate::bin_image::*;
 use crate::common::*;
+use crate::neighborhood::Neighborhood;
+use crate::options::ThinOptions;
+#[cfg(test)]
+use crate::thickness::check_thickness;
+#[cfg(test)]
+use crate::topology::verify_topology;
+#[cfg(test)]
 use std::convert::TryFrom;
 use std::io::Error;
 #[cfg(test)]
 use std::path::PathBuf;
-
-fn sub_iter(
-    mode: &SubIter,
-    p2: bool,
-    p3: bool,
-    p4: bool,
-    p5: bool,
-    p6: bool,
-    p7: bool,
-    p8: bool,
-    p9: bool,
-) -> bool {
-    let (a_p, b_p) = calculate_ap_and_bp(p2, p3, p4, p5, p6, p7, p8, p9);
+use std::sync::OnceLock;
+
+#[allow(clippy::manual_range_contains)]
+fn sub_iter(mode: &SubIter, n: Neighborhood) -> bool {
+    let (p2, p3, p4, p5, p6, p7, p8, p9) = (
+        n.p2(),
+        n.p3(),
+        n.p4(),
+        n.p5(),
+        n.p6(),
+        n.p7(),
+        n.p8(),
+        n.p9(),
+    );
+    let (a_p, b_p) = (n.transition_count(), n.count());
 
     let a = 2 <= b_p && b_p <= 7;
 
@@ -43,100 +52,77 @@ fn sub_iter(
 }
 
 fn make_table(sub_iter_type: SubIter) -> Vec<Vec<bool>> {
-    // Making the mapping table
-    let mut table: Vec<Vec<bool>> = vec![];
-
-    for i in 0..16 {
-        let i_bin = format!("{:04b}", i);
-        let mut row: Vec<bool> = vec![];
-        for j in 0..16 {
-            let j_bin = format!("{:04b}", j);
-            let i_bin = i_bin.as_str();
-            let bin: String = j_bin.clone() + i_bin;
-
-            let neighbors: Vec<bool> = bin
-                .chars()
-                .map(|c| match c {
-                    '0' => false,
-                    '1' => true,
-                    _ => panic!("Can not parse char to bool"),
-                })
-                .collect();
-
-            let sub_iter_result = !sub_iter(
-                &sub_iter_type,
-                neighbors[3],
-                neighbors[2],
-                neighbors[1],
-                neighbors[0],
-                neighbors[7],
-                neighbors[6],
-                neighbors[5],
-                neighbors[4],
-            );
-
-            row.push(sub_iter_result);
-        }
+    // Making the mapping table. Rows are the upper half of the neighborhood
+    // index, P6 to P9, and columns the lower half, P2 to P5.
+    (0..16)
+        .map(|i| {
+            (0..16)
+                .map(|j| !sub_iter(&sub_iter_type, Neighborhood::new(true, (i << 4 | j) as u8)))
+                .collect()
+        })
+        .collect()
+}
 
-        table.push(row);
-    }
+// Mapping tables of both sub-iterations, made once
+fn get_table(mode: &SubIter) -> &'static Vec<Vec<bool>> {
+    static TABLES: OnceLock<[Vec<Vec<bool>>; 2]> = OnceLock::new();
+    let tables = TABLES.get_or_init(|| [make_table(SubIter::First), make_table(SubIter::Second)]);
 
-    table
+    match mode {
+        SubIter::First => &tables[0],
+        SubIter::Second => &tables[1],
+    }
 }
 
-fn bin_to_dec(bin: [bool; 4]) -> usize {
-    let val: Vec<usize> = bin
-        .iter()
-        .map(|v| if *v { 1 as usize } else { 0 as usize })
-        .collect();
-
-    (val[3] * 1) + (val[2] * 2) + (val[1] * 4) + (val[0] * 8)
+// Options giving the result of `imgthin`
+pub(crate) fn imgthin_options() -> ThinOptions {
+    ThinOptions::default()
 }
 
 pub fn imgthin(pixels: Vec<Vec<bool>>) -> Result<Vec<Vec<bool>>, Error> {
-    let mut k_t = BinImage::try_from(pixels)?;
-    let mut s_t = k_t.clone();
-
-    let sub_1_table = make_table(SubIter::First);
-    let sub_2_table = make_table(SubIter::Second);
-
-    let mut k = SubIter::First;
-    let mut s = SubIter::Second;
-    let mut flag = true;
-
-    while flag {
-        flag = false;
-        let mut k_t_iter = k_t.into_iter();
-
-        while let Some((x, y, val)) = k_t_iter.next() {
-            if val {
-                let neighbors = s_t.get_neighbors(x, y);
-
-                let j = bin_to_dec([neighbors.4, neighbors.3, neighbors.2, neighbors.1]);
-                let i = bin_to_dec([neighbors.8, neighbors.7, neighbors.6, neighbors.5]);
-                let d_out = match k {
-                    SubIter::First => sub_1_table.get(i).unwrap().get(j).unwrap(),
-                    SubIter::Second => sub_2_table.get(i).unwrap().get(j).unwrap(),
-                };
-
-                s_t.set_value(x, y, d_out.to_owned())?;
-                if !d_out {
-                    flag = true;
-                }
-            } else {
-                s_t.set_value(x, y, false)?;
-            }
-        }
-        let t = k;
-        k = s;
-        s = t;
-        k_t = s_t.clone();
+    imgthin_with_options(pixels, &imgthin_options())
+}
+
+// Whether a pixel is deleted in the sub-iteration `s`, counted from 0
+fn is_deletable(image: &BinImage, s: usize, x: usize, y: usize, options: &ThinOptions) -> bool {
+    let mode = if s.is_multiple_of(2) {
+        SubIter::First
+    } else {
+        SubIter::Second
+    };
+
+    // Pixels outside of the region and anchors are kept as they are
+    if !options.can_delete(x, y) || !image.get_value(x, y).unwrap() {
+        return false;
     }
 
-    Ok(s_t.get_pixels().to_vec())
+    let index = image.get_neighbors(x, y).index();
+
+    !get_table(&mode)[index >> 4][index & 15]
+}
+
+// Whether thinning stops after the sub-iteration `s` deleted `deleted` pixels
+fn is_converged(_s: usize, deleted: usize) -> bool {
+    deleted == 0
+}
+
+// Conditions of the thinning of `imgthin_with_options`
+pub(crate) const CONDITIONS: Conditions = Conditions {
+    is_deletable,
+    is_converged,
+    sequential: true,
+    keep_components: true,
+};
+
+pub fn imgthin_with_options(
+    pixels: Vec<Vec<bool>>,
+    options: &ThinOptions,
+) -> Result<Vec<Vec<bool>>, Error> {
+    thin(pixels, options, &CONDITIONS, None, |_, _, _| {}).map(|(pixels, _)| pixels)
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod test {
     use super::*;
 
@@ -145,14 +131,7 @@ mod test {
         assert_eq!(
             sub_iter(
                 &SubIter::First,
-                true,
-                true,
-                true,
-                true,
-                true,
-                true,
-                true,
-                true
+                Neighborhood::from_array(true, [true, true, true, true, true, true, true, true])
             ),
             false
         );
@@ -160,14 +139,7 @@ mod test {
         assert_eq!(
             sub_iter(
                 &SubIter::First,
-                true,
-                true,
-                false,
-                true,
-                true,
-                true,
-                true,
-                true
+                Neighborhood::from_array(true, [true, true, false, true, true, true, true, true])
             ),
             true
         );
@@ -175,14 +147,10 @@ mod test {
         assert_eq!(
             sub_iter(
                 &SubIter::First,
-                true,
-                false,
-                true,
-                true,
-                false,
-                false,
-                false,
-                false
+                Neighborhood::from_array(
+                    true,
+                    [true, false, true, true, false, false, false, false]
+                )
             ),
             true
         );
@@ -190,14 +158,7 @@ mod test {
         assert_eq!(
             sub_iter(
                 &SubIter::First,
-                true,
-                true,
-                true,
-                true,
-                false,
-                false,
-                false,
-                true
+                Neighborhood::from_array(true, [true, true, true, true, false, false, false, true])
             ),
             true
         );
@@ -205,14 +166,10 @@ mod test {
         assert_eq!(
             sub_iter(
                 &SubIter::First,
-                true,
-                false,
-                true,
-                false,
-                false,
-                false,
-                false,
-                false
+                Neighborhood::from_array(
+                    true,
+                    [true, false, true, false, false, false, false, false]
+                )
             ),
             true
         );
@@ -220,14 +177,10 @@ mod test {
         assert_eq!(
             sub_iter(
                 &SubIter::First,
-                false,
-                false,
-                true,
-                false,
-                true,
-                false,
-                false,
-                false
+                Neighborhood::from_array(
+                    true,
+                    [false, false, true, false, true, false, false, false]
+                )
             ),
             true
         );
@@ -235,14 +188,7 @@ mod test {
         assert_eq!(
             sub_iter(
                 &SubIter::First,
-                false,
-                true,
-                true,
-                true,
-                true,
-                true,
-                false,
-                false
+                Neighborhood::from_array(true, [false, true, true, true, true, true, false, false])
             ),
             true
         );
@@ -250,14 +196,10 @@ mod test {
         assert_eq!(
             sub_iter(
                 &SubIter::Second,
-                false,
-                false,
-                false,
-                false,
-                true,
-                false,
-                true,
-                false
+                Neighborhood::from_array(
+                    true,
+                    [false, false, false, false, true, false, true, false]
+                )
             ),
             true
         );
@@ -265,14 +207,7 @@ mod test {
         assert_eq!(
             sub_iter(
                 &SubIter::Second,
-                false,
-                false,
-                false,
-                true,
-                true,
-                true,
-                true,
-                true
+                Neighborhood::from_array(true, [false, false, false, true, true, true, true, true])
             ),
             true
         );
@@ -282,22 +217,15 @@ mod test {
     fn test_direct_computation_vs_table() {
         let table_2 = make_table(SubIter::Second);
 
-        let j = bin_to_dec([false, true, true, true]);
-        let i = bin_to_dec([true, true, false, false]);
+        let j = 0b0111;
+        let i = 0b1100;
 
         let table_val = table_2.get(i).unwrap().get(j).unwrap();
 
         assert_eq!(
             sub_iter(
                 &SubIter::First,
-                false,
-                true,
-                true,
-                true,
-                true,
-                true,
-                false,
-                false
+                Neighborhood::from_array(true, [false, true, true, true, true, true, false, false])
             ),
             table_val.to_owned()
         );
@@ -320,13 +248,6 @@ mod test {
         assert_eq!(second_sub.get_pixels(), second_sub_expect.get_pixels());
     }
 
-    #[test]
-    fn test_bin_to_dec() {
-        assert_eq!(bin_to_dec([true, false, false, false]), 8);
-        assert_eq!(bin_to_dec([true, true, false, false]), 12);
-        assert_eq!(bin_to_dec([true, true, true, false]), 14);
-    }
-
     #[test]
     fn test_char_b() {
         let img = BinImage::try_from(PathBuf::from("./test_data/b_char.txt")).unwrap();
@@ -337,6 +258,69 @@ mod test {
 
         let expect_img =
             BinImage::try_from(PathBuf::from("./test_data/b_char_improved_thinned.txt")).unwrap();
-        assert_eq!(expect_img.get_pixels(), thinned_img.get_pixels());
+        crate::assert_bin_image_eq!(expect_img, thinned_img);
+        assert!(verify_topology(&img, &thinned_img).unwrap().is_preserved());
+    }
+
+    #[test]
+    fn test_char_b_unit_thickness() {
+        let img = BinImage::try_from(PathBuf::from("./test_data/b_char.txt")).unwrap();
+
+        let options = ThinOptions {
+            unit_thickness: true,
+            ..Default::default()
+        };
+        let thinned = imgthin_with_options(img.get_pixels().to_vec(), &options).unwrap();
+        let thinned_img = BinImage::try_from(thinned).unwrap();
+
+        assert!(check_thickness(&thinned_img).is_unit_thick());
+        assert!(verify_topology(&img, &thinned_img).unwrap().is_preserved());
+    }
+
+    #[test]
+    fn test_char_b_sequential() {
+        let img = BinImage::try_from(PathBuf::from("./test_data/b_char.txt")).unwrap();
+
+        let options = ThinOptions {
+            sequential: true,
+            ..ThinOptions::default()
+        };
+        let thinned = imgthin_with_options(img.get_pixels().to_vec(), &options).unwrap();
+
+        let expect_img = BinImage::try_from(PathBuf::from(
+            "./test_data/b_char_improved_sequential_thinned.txt",
+        ))
+        .unwrap();
+        crate::assert_bin_image_eq!(expect_img, BinImage::try_from(thinned.clone()).unwrap());
+
+        // Sub-iterations reading their own deletions thin the strokes
+        // unevenly, towards the end of the scan
+        let parallel = imgthin(img.get_pixels().to_vec()).unwrap();
+        assert_ne!(parallel, thinned);
+        assert!(thinned[3].iter().all(|val| !val));
+        assert!(parallel[3].iter().any(|val| *val));
+    }
+
+    #[test]
+    fn test_small_squares() {
+        let img = BinImage::try_from(PathBuf::from("./test_data/small_squares.txt")).unwrap();
+
+        // Every square and the bar keep a pixel, although all their pixels
+        // are deletable in the last sub-iteration
+        let thinned = BinImage::try_from(imgthin(img.get_pixels().to_vec()).unwrap()).unwrap();
+        let expect_img = BinImage::try_from(PathBuf::from(
+            "./test_data/small_squares_improved_thinned.txt",
+        ))
+        .unwrap();
+        crate::assert_bin_image_eq!(expect_img, thinned);
+        assert!(verify_topology(&img, &thinned).unwrap().is_preserved());
+
+        let options = ThinOptions {
+            sequential: true,
+            ..ThinOptions::default()
+        };
+        let sequential = imgthin_with_options(img.get_pixels().to_vec(), &options).unwrap();
+        let sequential = BinImage::try_from(sequential).unwrap();
+        assert!(verify_topology(&img, &sequential).unwrap().is_preserved());
     }
 }